@@ -4,25 +4,87 @@ use ink_lang as ink;
 
 pub use self::authority_management::{
     AuthorityManagement,
+    PrivilegeInfo,
+    Error,
+    Result,
 };
 #[allow(unused_imports)]
 #[ink::contract]
 mod authority_management {
     use alloc::string::String;
     use ink_prelude::vec::Vec;
-    use ink_storage::{collections::HashMap as StorageHashMap, };
+    use ink_storage::{
+        collections::HashMap as StorageHashMap,
+        traits::{
+            PackedLayout,
+            SpreadLayout,
+        }
+    };
+
+    /// Details of a privilege
+    /// #Fields
+    /// index:Privilege index
+    /// name:Name of the privilege, e.g. erc20_factory.new_erc20
+    /// description:What the privilege allows
+    /// module:The module that registered the privilege, empty for protocol wide privileges
+    #[derive(scale::Encode, scale::Decode, Clone, SpreadLayout, PackedLayout)]
+    #[cfg_attr(
+    feature = "std",
+    derive(scale_info::TypeInfo, ink_storage::traits::StorageLayout)
+    )]
+    #[derive(Debug)]
+    pub struct PrivilegeInfo {
+        pub index: u64,
+        pub name: String,
+        pub description: String,
+        pub module: String,
+    }
+
+    /// The AuthorityManagement error types.
+    #[derive(Debug, PartialEq, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub enum Error {
+        /// Returned if the caller is not the core contract.
+        NotCore,
+        /// Returned if the privilege has not been added.
+        PrivilegeNotFound,
+        /// Returned if a privilege with the same name has been added.
+        DuplicatePrivilege,
+    }
+
+    /// The AuthorityManagement result type.
+    pub type Result<T> = core::result::Result<T, Error>;
+
+    #[ink(event)]
+    pub struct PrivilegeAdded {
+        #[ink(topic)]
+        name: String,
+        index: u64,
+        #[ink(topic)]
+        module: String,
+    }
 
+    #[ink(event)]
+    pub struct PrivilegeRemoved {
+        #[ink(topic)]
+        name: String,
+        index: u64,
+    }
 
     /// Authority management contract of rainbow protocol
     /// #Fields
     /// owner:The manager of this contract
     /// index:Authority index
     /// privilege_map:HashMap of authority index and name
+    /// privilege_info:HashMap of authority name and details
+    /// module_privileges:HashMap of module name and the names of its privileges
     #[ink(storage)]
     pub struct AuthorityManagement {
         owner:AccountId,
         index:u64,
         privilege_map:StorageHashMap<u64,String>,
+        privilege_info:StorageHashMap<String,PrivilegeInfo>,
+        module_privileges:StorageHashMap<String,Vec<String>>,
     }
 
     impl AuthorityManagement {
@@ -32,25 +94,94 @@ mod authority_management {
                 owner:Self::env().caller(),
                 index: 0,
                 privilege_map : StorageHashMap::new(),
+                privilege_info : StorageHashMap::new(),
+                module_privileges : StorageHashMap::new(),
             };
             instance
         }
 
-        fn only_core(&self,sender:AccountId) {
-            assert_eq!(self.owner, sender);
+        fn only_core(&self,sender:AccountId) -> Result<()> {
+            if self.owner != sender {
+                return Err(Error::NotCore);
+            }
+            Ok(())
         }
        /// Add a new privilege
        /// #Fields
        /// name:Name of the privilege
-       /// #Panics
-       /// Only core contracts can be called
+       /// #Errors
+       /// NotCore if not called by the core contract, DuplicatePrivilege if the name is taken
+        #[ink(message)]
+        pub fn add_privilege(&mut self, name: String) -> Result<()> {
+            self.add_module_privilege(name, String::default(), String::default())
+        }
+       /// Add a new privilege that belongs to a module.
+       /// Modules register their privileges this way when they are installed
+       /// #Fields
+       /// name:Name of the privilege, by convention prefixed with the module, e.g. erc20_factory.new_erc20
+       /// description:What the privilege allows
+       /// module:The module that registers the privilege
+       /// #Errors
+       /// NotCore if not called by the core contract, DuplicatePrivilege if the name is taken
         #[ink(message)]
-        pub fn add_privilege(&mut self, name: String) -> bool {
-            self.only_core(Self::env().caller());
+        pub fn add_module_privilege(&mut self, name: String, description: String, module: String) -> Result<()> {
+            self.only_core(Self::env().caller())?;
             assert_eq!(self.index + 1 > self.index, true);
-            self.privilege_map.insert(self.index, name);
+            if self.privilege_info.contains_key(&name) {
+                return Err(Error::DuplicatePrivilege);
+            }
+            let index = self.index;
+            self.privilege_map.insert(index, name.clone());
+            self.privilege_info.insert(name.clone(), PrivilegeInfo {
+                index,
+                name: name.clone(),
+                description,
+                module: module.clone(),
+            });
+            if !module.is_empty() {
+                self.module_privileges.entry(module.clone()).or_insert(Vec::new()).push(name.clone());
+            }
+            self.env().emit_event(PrivilegeAdded { name, index, module });
             self.index += 1;
-            true
+            Ok(())
+        }
+        /// Delete a privilege
+        /// #Fields
+        /// name:Name of the privilege
+        /// #Errors
+        /// NotCore if not called by the core contract, PrivilegeNotFound if the privilege does not exist
+        #[ink(message)]
+        pub fn remove_privilege(&mut self, name: String) -> Result<()> {
+            self.only_core(Self::env().caller())?;
+            let info = self.privilege_info.take(&name).ok_or(Error::PrivilegeNotFound)?;
+            let privilege_index = info.index;
+            self.privilege_map.take(&privilege_index);
+            if let Some(privileges) = self.module_privileges.get_mut(&info.module) {
+                privileges.retain(|privilege| *privilege != name);
+            }
+            self.env().emit_event(PrivilegeRemoved { name, index: privilege_index });
+            Ok(())
+        }
+        /// Check whether a privilege has been added
+        /// #Fields
+        /// name:Name of the privilege
+        #[ink(message)]
+        pub fn exists_privilege(&self, name: String) -> bool {
+            self.privilege_info.contains_key(&name)
+        }
+        /// Query the details of a privilege by name
+        /// #Fields
+        /// name:Name of the privilege
+        #[ink(message)]
+        pub fn query_privilege_by_name(&self, name: String) -> Option<PrivilegeInfo> {
+            self.privilege_info.get(&name).cloned()
+        }
+        /// Show the names of all privileges registered by a module
+        /// #Fields
+        /// module:The name of module
+        #[ink(message)]
+        pub fn list_module_privileges(&self, module: String) -> Vec<String> {
+            self.module_privileges.get(&module).cloned().unwrap_or(Vec::new())
         }
         /// All privilege names are displayed
         #[ink(message)]
@@ -64,12 +195,12 @@ mod authority_management {
             }
             privilege_vec
         }
-        /// Query the name of the privilege by index
+        /// Query the name of the privilege by index, it is empty for an unknown index
         /// #Fields
         /// index:Privilege index
         #[ink(message)]
         pub fn query_privilege_by_index(&self, index: u64) -> String {
-            self.privilege_map.get(&index).unwrap().clone()
+            self.privilege_map.get(&index).cloned().unwrap_or(String::default())
         }
 
     }
@@ -85,11 +216,44 @@ mod authority_management {
         /// Imports `ink_lang` so we can use `#[ink::test]`.
         use ink_lang as ink;
 
+        type Event = <AuthorityManagement as ::ink_lang::BaseEvent>::Type;
+
         #[ink::test]
         fn init_works() {
             let mut authority_management = AuthorityManagement::new();
-            authority_management.add_privilege(String::from("test"));
+            assert_eq!(authority_management.add_privilege(String::from("test")), Ok(()));
             assert!(authority_management.query_privilege_by_index(0)== String::from("test"));
+            assert!(authority_management.query_privilege_by_index(1)== String::default());
+        }
+        #[ink::test]
+        fn module_privilege_works() {
+            let mut authority_management = AuthorityManagement::new();
+            assert_eq!(authority_management.add_module_privilege(
+                String::from("erc20_factory.new_erc20"),
+                String::from("create tokens"),
+                String::from("erc20_factory")
+            ), Ok(()));
+            assert_eq!(authority_management.add_privilege(String::from("erc20_factory.new_erc20")), Err(Error::DuplicatePrivilege));
+            let info = authority_management.query_privilege_by_name(String::from("erc20_factory.new_erc20")).unwrap();
+            assert_eq!(info.index, 0);
+            assert!(info.description == String::from("create tokens"));
+            assert_eq!(authority_management.list_module_privileges(String::from("erc20_factory")).len(), 1);
+            assert_eq!(authority_management.remove_privilege(String::from("erc20_factory.new_erc20")), Ok(()));
+            assert!(authority_management.list_module_privileges(String::from("erc20_factory")).is_empty());
+            assert!(authority_management.query_privilege_by_name(String::from("erc20_factory.new_erc20")).is_none());
+        }
+        #[ink::test]
+        fn remove_privilege_works() {
+            let mut authority_management = AuthorityManagement::new();
+            assert_eq!(authority_management.add_privilege(String::from("test")), Ok(()));
+            assert_eq!(authority_management.remove_privilege(String::from("test")), Ok(()));
+            assert!(authority_management.list_privileges().is_empty());
+            assert_eq!(authority_management.remove_privilege(String::from("test")), Err(Error::PrivilegeNotFound));
+            let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+            assert_eq!(emitted_events.len(), 2);
+            let decoded_event = <Event as scale::Decode>::decode(&mut &emitted_events[1].data[..])
+                .expect("encountered invalid contract event data buffer");
+            assert!(matches!(decoded_event, Event::PrivilegeRemoved(PrivilegeRemoved { index: 0, .. })));
         }
     }
 }
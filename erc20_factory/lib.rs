@@ -7,10 +7,12 @@ mod erc20_factory {
     use erc20::Erc20;
     use income_category::IncomeCategory;
     use route_manage::RouteManage;
+    use users_manage::UsersManage;
     use alloc::string::String;
     use ink_prelude::vec::Vec;
     use ink_storage::{collections::HashMap as StorageHashMap, };
     const CONTRACT_INIT_BALANCE: u128 = 1000 * 1_000_000_000_000;
+    const INCOME_CATEGORY_INTERFACE: &str = "income_category";
 
     /// Erc20Factory contract of rainbow protocol
     /// #Fields
@@ -41,6 +43,8 @@ mod erc20_factory {
        /// symbol:the symbol of token
        /// decimals:the decimals of token
        /// owner:the manager of token
+       /// # Panics
+       /// The income_category route exists but is not registered with the income_category interface
         #[ink(message)]
         pub fn new_erc20(
             &mut self,
@@ -60,7 +64,11 @@ mod erc20_factory {
                 .params();
             let init_result = ink_env::instantiate_contract(&instance_params);
             let contract_addr = init_result.expect("failed at instantiating the `Erc20` contract");
-            let income_category_addr =  self.get_contract_addr(String::from("income_category"));
+            let route_instance: RouteManage = ink_env::call::FromAccountId::from_account_id(self.route_addr);
+            let income_category_addr = route_instance.query_checked_route(
+                String::from("income_category"),
+                String::from(INCOME_CATEGORY_INTERFACE)
+            );
             if income_category_addr != AccountId::default()  {
                 self.send_income_fee(income_category_addr);
             }
@@ -90,18 +98,34 @@ mod erc20_factory {
             }
             token_vec
         }
+        /// Take the erc20 fee from the caller, the referers of the caller get their share
+        /// only when this contract is a fee collector of users_manage
         fn send_income_fee(&mut self,income_category_addr:AccountId) -> bool {
             let mut income_instance: IncomeCategory = ink_env::call::FromAccountId::from_account_id(income_category_addr);
             let category =  income_instance.get_category(String::from("erc20"));
             if category.is_used {
-                self.get_fee_from_user(category.token,category.fee,income_category_addr);
+                let caller = Self::env().caller();
+                let users_manage_addr = self.get_contract_addr(String::from("users_manage"));
+                let mut referral_share = 0;
+                if users_manage_addr != AccountId::default() {
+                    let users_instance: UsersManage = ink_env::call::FromAccountId::from_account_id(users_manage_addr);
+                    if users_instance.is_fee_collector(self.env().account_id()) {
+                        referral_share = users_instance.referral_share(caller,category.fee);
+                    }
+                }
+                self.get_fee_from_user(category.token,category.fee - referral_share,income_category_addr);
+                if referral_share > 0 {
+                    self.get_fee_from_user(category.token,referral_share,users_manage_addr);
+                    let mut users_instance: UsersManage = ink_env::call::FromAccountId::from_account_id(users_manage_addr);
+                    users_instance.distribute_referral_reward(caller,category.token,category.fee);
+                }
             }
             true
         }
 
         fn get_fee_from_user(&mut self,token_account:AccountId,fee:u128,to_account:AccountId) -> bool {
             let mut erc20_instance: Erc20 = ink_env::call::FromAccountId::from_account_id(token_account);
-            erc20_instance.transfer_from(Self::env().caller(),to_account,fee);
+            assert!(erc20_instance.transfer_from(Self::env().caller(),to_account,fee), "fee not paid");
             true
         }
         /// Get the address of a contract
@@ -110,5 +134,12 @@ mod erc20_factory {
             let route_instance: RouteManage = ink_env::call::FromAccountId::from_account_id(self.route_addr);
             return route_instance.query_route_by_name(target_name);
         }
+        /// Get the address of a contract, the default address is returned when
+        /// the route is registered with another interface or without metadata
+        #[ink(message)]
+        pub fn get_typed_contract_addr(&self,target_name:String,interface_id:String) ->AccountId {
+            let route_instance: RouteManage = ink_env::call::FromAccountId::from_account_id(self.route_addr);
+            return route_instance.query_typed_route(target_name,interface_id);
+        }
     }
 }
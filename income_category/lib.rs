@@ -9,6 +9,7 @@ use ink_lang as ink;
 mod income_category {
     use alloc::string::String;
     use ink_prelude::collections::BTreeMap;
+    use kernel::Kernel;
     use ink_storage::{
         collections::HashMap as StorageHashMap,
         traits::{
@@ -32,13 +33,18 @@ mod income_category {
        pub fee: u128,
        pub token: AccountId
     }
+    /// The privilege checked through the kernel to manage categories
+    const MANAGE_PRIVILEGE: &str = "income_category.manage";
+
     /// All charging types of rainbow agreement are set here
     /// owner:The manager of this contract
     /// category:HashMap of category name and detail
+    /// kernel_addr:the address of kernel, accounts it authorizes can manage categories
     #[ink(storage)]
     pub struct IncomeCategory {
         owner:AccountId,
         category:StorageHashMap<String, IncomeInfo>,
+        kernel_addr:AccountId,
     }
 
     impl IncomeCategory {
@@ -46,7 +52,8 @@ mod income_category {
         pub fn new(owner:AccountId) -> Self {
             Self {
                 owner,
-                category: StorageHashMap::new()
+                category: StorageHashMap::new(),
+                kernel_addr: AccountId::default(),
             }
         }
         ///Add a category
@@ -55,7 +62,7 @@ mod income_category {
         #[ink(message)]
         #[ink(selector = 0xDEADBEEF)]
         pub fn save_category(&mut self,name:String,income:IncomeInfo) -> bool {
-            self.only_owner(Self::env().caller());
+            self.only_authorized(Self::env().caller());
             self.category.insert(name,income);
             true
         }
@@ -77,6 +84,21 @@ mod income_category {
         }
 
 
+        /// Let the kernel decide who can manage categories
+        /// kernel_addr:the address of kernel
+        #[ink(message)]
+        pub fn set_kernel(&mut self,kernel_addr:AccountId) -> bool {
+            self.only_owner(Self::env().caller());
+            self.kernel_addr = kernel_addr;
+            true
+        }
+
+        /// Get the address of kernel
+        #[ink(message)]
+        pub fn get_kernel(&self) -> AccountId {
+            self.kernel_addr
+        }
+
         /// Show all category
         #[ink(message)]
         pub fn list_category(&self) -> BTreeMap<String,IncomeInfo> {
@@ -99,6 +121,15 @@ mod income_category {
         fn only_owner(&self,sender:AccountId) {
             assert_eq!(self.owner, sender);
         }
+
+        fn only_authorized(&self,sender:AccountId) {
+            if self.owner != AccountId::default() && self.owner == sender {
+                return;
+            }
+            assert!(self.kernel_addr != AccountId::default());
+            let kernel_instance: Kernel = ink_env::call::FromAccountId::from_account_id(self.kernel_addr);
+            assert!(kernel_instance.is_authorized(sender, String::from(MANAGE_PRIVILEGE)));
+        }
     }
     #[cfg(test)]
     mod tests {
@@ -121,5 +152,15 @@ mod income_category {
             let income:IncomeInfo =income_category.get_category(String::from("test"));
             assert!(income.fee == 1);
         }
+
+        #[ink::test]
+        #[should_panic]
+        fn save_category_fails_without_kernel() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                    .expect("Cannot get accounts");
+            let mut income_category = IncomeCategory::new(accounts.bob);
+            income_category.save_category(String::from("test"),IncomeInfo{is_used:false,fee:1,token:AccountId::from([0x01; 32])});
+        }
     }
 }
@@ -8,10 +8,82 @@ pub use self::kernel::{
 #[ink::contract]
 mod kernel {
     use alloc::string::String;
+    use ink_prelude::vec::Vec;
+    use ink_prelude::format;
+    use ink_storage::{
+        collections::HashMap as StorageHashMap,
+        traits::{
+            PackedLayout,
+            SpreadLayout,
+        }
+    };
     use role_manage::RoleManage;
     use route_manage::RouteManage;
     use authority_management::AuthorityManagement;
     const DAO_INIT_BALANCE: u128 = 1000 * 1_000_000_000_000;
+    const KERNEL: &str = "kernel";
+    const ROLE_MANAGE: &str = "role_manage";
+    const AUTHORITY_MANAGEMENT: &str = "authority_management";
+    const ROUTE_MANAGE: &str = "route_manage";
+
+    /// A deployed version of a managed contract
+    /// version:the version number, starting at 1
+    /// code_hash:the code hash the contract runs
+    /// addr:the address of contract
+    /// block:the block number when it was deployed
+    #[derive(scale::Encode, scale::Decode, Clone, SpreadLayout, PackedLayout)]
+    #[cfg_attr(
+    feature = "std",
+    derive(scale_info::TypeInfo, ink_storage::traits::StorageLayout)
+    )]
+    #[derive(Debug)]
+    pub struct ModuleVersion {
+        pub version: u32,
+        pub code_hash: Hash,
+        pub addr: AccountId,
+        pub block: u32,
+    }
+
+    /// An administrative operation that can be run in a batch
+    #[derive(scale::Encode, scale::Decode, Clone)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    #[derive(Debug)]
+    pub enum AdminOperation {
+        /// Add a role (role name)
+        AddRole(String),
+        /// Remove a role (role name)
+        RemoveRole(String),
+        /// Add a privilege for a role (role name, privilege name)
+        RoleInsertPrivilege(String, String),
+        /// Remove a privilege from a role (role name, privilege name)
+        RoleRemovePrivilege(String, String),
+        /// Let a role inherit from a parent role (role name, parent role name)
+        AddRoleParent(String, String),
+        /// Stop a role inheriting from a parent role (role name, parent role name)
+        RemoveRoleParent(String, String),
+        /// Give a role to user (user, role name)
+        AddUserRole(AccountId, String),
+        /// Give a role to user until a block (user, role name, expire block)
+        AddTemporaryUserRole(AccountId, String, u32),
+        /// Take a role back from user (user, role name)
+        RemoveUserRole(AccountId, String),
+        /// Add a privilege (privilege name)
+        AddPrivilege(String),
+        /// Add a privilege that belongs to a module (privilege name, description, module name)
+        AddModulePrivilege(String, String, String),
+        /// Delete a privilege (privilege name)
+        RemovePrivilege(String),
+        /// Add a route (route name, address)
+        AddRoute(String, AccountId),
+        /// Change routing address (route name, address)
+        ChangeRoute(String, AccountId),
+        /// Point a route back to an earlier version (route name, version)
+        RollbackRoute(String, u32),
+        /// Set the metadata of a route (route name, code hash, interface id, description)
+        SetRouteInfo(String, Hash, String, String),
+        /// Delete a route (route name)
+        RemoveRoute(String),
+    }
 
     /// This is the core of the rainbow agreement
     /// owner:the manager of this contract
@@ -22,16 +94,57 @@ mod kernel {
     /// authority_management : the authority_management's instance
     /// authority_management_addr : the authority_management's address
     /// init : Has the contract been activated
+    /// pending_owner : the owner proposed by the current owner, waiting to accept
+    /// module_versions : HashMap of managed contract name and its deployed versions
     #[ink(storage)]
     pub struct Kernel {
         owner:AccountId,
+        pending_owner:Option<AccountId>,
         role_manage: Option<RoleManage>,
         role_manage_addr: AccountId,
         route_manage: Option<RouteManage>,
         route_manage_addr: AccountId,
         authority_management:Option<AuthorityManagement>,
         authority_management_addr:AccountId,
-        init : bool
+        init : bool,
+        module_versions:StorageHashMap<String,Vec<ModuleVersion>>,
+    }
+
+    /// Emitted when a batch of administrative operations has been applied
+    #[ink(event)]
+    pub struct BatchExecuted {
+        #[ink(topic)]
+        caller: AccountId,
+        count: u32,
+    }
+
+    /// Emitted when a managed contract is deployed or upgraded
+    #[ink(event)]
+    pub struct ModuleUpgraded {
+        #[ink(topic)]
+        module: String,
+        version: u32,
+        code_hash: Hash,
+        addr: AccountId,
+    }
+
+    /// Emitted when the owner proposes a new owner
+    #[ink(event)]
+    pub struct OwnershipTransferProposed {
+        #[ink(topic)]
+        owner: AccountId,
+        #[ink(topic)]
+        pending_owner: AccountId,
+    }
+
+    /// Emitted when the ownership of the kernel changes.
+    /// new_owner is None when the ownership has been renounced
+    #[ink(event)]
+    pub struct OwnershipTransferred {
+        #[ink(topic)]
+        previous_owner: AccountId,
+        #[ink(topic)]
+        new_owner: Option<AccountId>,
     }
 
     impl Kernel {
@@ -39,54 +152,262 @@ mod kernel {
         pub fn new() -> Self {
             let instance = Self {
                 owner:Self::env().caller(),
+                pending_owner : None,
                 role_manage : None,
                 role_manage_addr : AccountId::default(),
                 route_manage : None,
                 route_manage_addr : AccountId::default(),
                 authority_management : None,
                 authority_management_addr : AccountId::default(),
-                init:false
+                init:false,
+                module_versions : StorageHashMap::new(),
             };
             instance
         }
+
+        fn only_owner(&self,sender:AccountId) {
+            assert_eq!(self.owner, sender);
+        }
+
+        /// Get the owner of kernel
+        #[ink(message)]
+        pub fn get_owner(&self) -> AccountId {
+            self.owner
+        }
+        /// Get the owner waiting to accept the ownership
+        #[ink(message)]
+        pub fn get_pending_owner(&self) -> Option<AccountId> {
+            self.pending_owner
+        }
+        /// Propose a new owner, e.g. the address of govnance_dao or multisig.
+        /// The new owner must call accept_owner to take over
+        /// new_owner : the address of new owner
+        /// # Panics
+        /// Only the owner can be called
+        #[ink(message)]
+        pub fn propose_owner(&mut self, new_owner: AccountId) -> bool {
+            self.only_owner(Self::env().caller());
+            assert!(new_owner != AccountId::default());
+            self.pending_owner = Some(new_owner);
+            self.env().emit_event(OwnershipTransferProposed {
+                owner: self.owner,
+                pending_owner: new_owner,
+            });
+            true
+        }
+        /// Accept the ownership proposed by the current owner
+        /// # Panics
+        /// Only the pending owner can be called
+        #[ink(message)]
+        pub fn accept_owner(&mut self) -> bool {
+            let caller = Self::env().caller();
+            assert_eq!(self.pending_owner, Some(caller));
+            let previous_owner = self.owner;
+            self.owner = caller;
+            self.pending_owner = None;
+            self.env().emit_event(OwnershipTransferred {
+                previous_owner,
+                new_owner: Some(caller),
+            });
+            true
+        }
+        /// Give up the ownership forever, no message that needs the owner can be called any more
+        /// # Panics
+        /// Only the owner can be called
+        #[ink(message)]
+        pub fn renounce_owner(&mut self) -> bool {
+            self.only_owner(Self::env().caller());
+            let previous_owner = self.owner;
+            self.owner = AccountId::default();
+            self.pending_owner = None;
+            self.env().emit_event(OwnershipTransferred {
+                previous_owner,
+                new_owner: None,
+            });
+            true
+        }
         /// Add a role
         /// name : the name of role
         #[ink(message)]
-        pub fn add_role(&mut self, name: String) {
-            // self.role_manage.add_role(name);
-            self.role_manage.as_mut().unwrap().add_role(name);
-
+        pub fn add_role(&mut self, name: String) -> role_manage::Result<()> {
+            self.only_owner(Self::env().caller());
+            self.role_manage.as_mut().unwrap().add_role(name)
         }
         /// Add a privilege for a role
         /// name : the name of role
+        /// privilege : the name of privilege, it must have been added by add_privilege
+        #[ink(message)]
+        pub fn role_insert_privilege(&mut self, name:String,privilege:String) -> role_manage::Result<()> {
+            self.only_owner(Self::env().caller());
+            self.role_manage.as_mut().unwrap().role_insert_privilege(name,privilege)
+        }
+        /// Remove a role
+        /// name : the name of role
+        #[ink(message)]
+        pub fn remove_role(&mut self, name: String) -> role_manage::Result<()> {
+            self.only_owner(Self::env().caller());
+            self.role_manage.as_mut().unwrap().remove_role(name)
+        }
+        /// Remove a privilege from a role
+        /// name : the name of role
         /// privilege : the name of privilege
         #[ink(message)]
-        pub fn role_insert_privilege(&mut self, name:String,privilege:String) {
-            // self.role_manage.role_insert_privilege(name,privilege);
-            self.role_manage.as_mut().unwrap().role_insert_privilege(name,privilege);
+        pub fn role_remove_privilege(&mut self, name:String,privilege:String) -> role_manage::Result<()> {
+            self.only_owner(Self::env().caller());
+            self.role_manage.as_mut().unwrap().role_remove_privilege(name,privilege)
+        }
+        /// Let a role inherit all privileges of a parent role
+        /// name : the name of role
+        /// parent : the name of parent role
+        #[ink(message)]
+        pub fn add_role_parent(&mut self, name:String,parent:String) -> role_manage::Result<()> {
+            self.only_owner(Self::env().caller());
+            self.role_manage.as_mut().unwrap().add_role_parent(name,parent)
+        }
+        /// Stop a role inheriting from a parent role
+        /// name : the name of role
+        /// parent : the name of parent role
+        #[ink(message)]
+        pub fn remove_role_parent(&mut self, name:String,parent:String) -> role_manage::Result<()> {
+            self.only_owner(Self::env().caller());
+            self.role_manage.as_mut().unwrap().remove_role_parent(name,parent)
+        }
+        /// Give a role to user
+        /// user : the address of user
+        /// role : the name of role
+        #[ink(message)]
+        pub fn add_user_role(&mut self, user: AccountId,role:String) -> role_manage::Result<()> {
+            self.only_owner(Self::env().caller());
+            self.role_manage.as_mut().unwrap().add_user_role(user,role)
+        }
+        /// Give a role to user that expires at a block
+        /// user : the address of user
+        /// role : the name of role
+        /// expire_block : the grant is ignored from this block on
+        #[ink(message)]
+        pub fn add_temporary_user_role(&mut self, user: AccountId,role:String,expire_block:u32) -> role_manage::Result<()> {
+            self.only_owner(Self::env().caller());
+            self.role_manage.as_mut().unwrap().add_temporary_user_role(user,role,expire_block)
+        }
+        /// Take a role back from user
+        /// user : the address of user
+        /// role : the name of role
+        #[ink(message)]
+        pub fn remove_user_role(&mut self, user: AccountId,role:String) -> role_manage::Result<()> {
+            self.only_owner(Self::env().caller());
+            self.role_manage.as_mut().unwrap().remove_user_role(user,role)
         }
         /// Add a privilege
         /// name : the name of privilege
         #[ink(message)]
-        pub fn add_privilege(&mut self, name: String) {
-            // self.authority_management.add_privilege(name);
-            self.authority_management.as_mut().unwrap().add_privilege(name);
+        pub fn add_privilege(&mut self, name: String) -> authority_management::Result<()> {
+            self.only_owner(Self::env().caller());
+            self.authority_management.as_mut().unwrap().add_privilege(name)
+        }
+        /// Add a privilege that belongs to a module
+        /// name : the name of privilege, e.g. erc20_factory.new_erc20
+        /// description : what the privilege allows
+        /// module : the module that registers the privilege
+        #[ink(message)]
+        pub fn add_module_privilege(&mut self, name: String,description: String,module: String) -> authority_management::Result<()> {
+            self.only_owner(Self::env().caller());
+            self.authority_management.as_mut().unwrap().add_module_privilege(name,description,module)
+        }
+        /// Delete a privilege and take it away from every role that has it
+        /// name : the name of privilege
+        #[ink(message)]
+        pub fn remove_privilege(&mut self, name: String) -> authority_management::Result<()> {
+            self.only_owner(Self::env().caller());
+            self.delete_privilege(name)
         }
         /// Add a route
         /// name : the name of route
         /// value : the address of route
         #[ink(message)]
         pub fn add_route(&mut self, name: String,value: AccountId) {
+            self.only_owner(Self::env().caller());
             // self.route_manage.add_route(name,value);
             self.route_manage.as_mut().unwrap().add_route(name,value);
         }
         /// Change routing address
         /// name : the name of route
         /// value : the address of route
+        /// # Panics
+        /// Panics if the route does not exist
         #[ink(message)]
         pub fn change_route(&mut self, name: String,value: AccountId) {
+            self.only_owner(Self::env().caller());
             // self.route_manage.add_route(name,value);
-            self.route_manage.as_mut().unwrap().change_route(name,value);
+            assert!(self.route_manage.as_mut().unwrap().change_route(name,value), "route does not exist");
+        }
+        /// Point a route back to the address of an earlier version
+        /// name : the name of route
+        /// version : the version to roll back to
+        /// # Panics
+        /// Panics if the route or the version does not exist
+        #[ink(message)]
+        pub fn rollback_route(&mut self, name: String,version: u32) {
+            self.only_owner(Self::env().caller());
+            assert!(self.route_manage.as_mut().unwrap().rollback_route(name,version), "route version does not exist");
+        }
+        /// Set the metadata of a route
+        /// name : the name of route
+        /// code_hash : the code hash the contract was deployed from
+        /// interface_id : the interface the contract implements
+        /// description : what the route is used for
+        /// # Panics
+        /// Panics if the route does not exist
+        #[ink(message)]
+        pub fn set_route_info(&mut self, name: String,code_hash: Hash,interface_id: String,description: String) {
+            self.only_owner(Self::env().caller());
+            assert!(self.route_manage.as_mut().unwrap().set_route_info(name,code_hash,interface_id,description), "route does not exist");
+        }
+        /// Delete a route
+        /// name : the name of route
+        #[ink(message)]
+        pub fn remove_route(&mut self, name: String) {
+            self.only_owner(Self::env().caller());
+            self.route_manage.as_mut().unwrap().remove_route(name);
+        }
+        /// Apply a list of administrative operations in order.
+        /// If any of them fails the whole call is reverted, so either all or none are applied
+        /// operations : the operations to apply
+        /// # Panics
+        /// Only the owner can be called, the panic message names the index and error of a failing operation
+        #[ink(message)]
+        pub fn batch(&mut self, operations: Vec<AdminOperation>) -> bool {
+            let caller = Self::env().caller();
+            self.only_owner(caller);
+            let count = operations.len() as u32;
+            for (index, operation) in operations.into_iter().enumerate() {
+                if let Err(error) = self.apply_operation(operation) {
+                    panic!("admin operation {} failed: {}", index, error);
+                }
+            }
+            self.env().emit_event(BatchExecuted {
+                caller,
+                count,
+            });
+            true
+        }
+        /// Check if an account has a privilege through its roles
+        /// account : the address to check
+        /// privilege : the name of privilege
+        #[ink(message)]
+        pub fn has_privilege(&self, account: AccountId, privilege: String) -> bool {
+            if !self.init {
+                return false;
+            }
+            self.role_manage.as_ref().unwrap().check_user_privilege(account, privilege)
+        }
+        /// Check if an account may perform an action guarded by a privilege.
+        /// The owner of kernel is always authorized, other contracts call this instead of keeping their own owner.
+        /// Nobody gets the owner shortcut once ownership is renounced
+        /// account : the address to check
+        /// privilege : the name of privilege
+        #[ink(message)]
+        pub fn is_authorized(&self, account: AccountId, privilege: String) -> bool {
+            (self.owner != AccountId::default() && account == self.owner) || self.has_privilege(account, privilege)
         }
         /// Get role's address
         #[ink(message)]
@@ -116,46 +437,282 @@ mod kernel {
             privilege_code_hash: Hash,
             route_code_hash: Hash
         ) -> bool {
+            self.only_owner(Self::env().caller());
             assert_eq!(self.init, false);
             let salt = version.to_le_bytes();
+            let role_manage_addr = self.instantiate_role_manage(salt, role_code_hash);
+            let role_contract_instance = ink_env::call::FromAccountId::from_account_id(role_manage_addr);
+            self.role_manage = Some(role_contract_instance);
+            self.role_manage_addr = role_manage_addr;
+            self.record_module_version(String::from(ROLE_MANAGE), role_code_hash, role_manage_addr);
+
+            let authority_management_addr = self.instantiate_authority_management(salt, privilege_code_hash);
+            let authority_contract_instance = ink_env::call::FromAccountId::from_account_id(authority_management_addr);
+            self.authority_management = Some(authority_contract_instance);
+            self.authority_management_addr = authority_management_addr;
+            self.role_manage.as_mut().unwrap().set_authority_management(authority_management_addr)
+                .expect("failed at setting up the `roleManager` contract");
+            self.record_module_version(String::from(AUTHORITY_MANAGEMENT), privilege_code_hash, authority_management_addr);
+
+            let route_manage_addr = self.instantiate_route_manage(salt, route_code_hash);
+            let route_contract_instance = ink_env::call::FromAccountId::from_account_id(route_manage_addr);
+            self.route_manage = Some(route_contract_instance);
+            self.route_manage_addr = route_manage_addr;
+            self.record_module_version(String::from(ROUTE_MANAGE), route_code_hash, route_manage_addr);
+
+            self.register_route(String::from(KERNEL), self.env().account_id());
+            self.register_module_route(String::from(ROLE_MANAGE), role_manage_addr, role_code_hash);
+            self.register_module_route(String::from(AUTHORITY_MANAGEMENT), authority_management_addr, privilege_code_hash);
+            self.register_module_route(String::from(ROUTE_MANAGE), route_manage_addr, route_code_hash);
+            self.init = true;
+            true
+        }
+        /// Deploy a new version of role_manage and move all roles, privileges and user roles into it
+        /// version:Random numbers are used to instantiate the contract
+        /// code_hash:the hash of the new role contract
+        /// # Panics
+        /// Only the owner can be called after the kernel has been initialized
+        #[ink(message)]
+        pub fn upgrade_role_manage(&mut self, version: u32, code_hash: Hash) -> AccountId {
+            self.only_owner(Self::env().caller());
+            assert_eq!(self.init, true);
+            let new_addr = self.instantiate_role_manage(version.to_le_bytes(), code_hash);
+            let mut new_instance: RoleManage = ink_env::call::FromAccountId::from_account_id(new_addr);
+            new_instance.set_authority_management(self.authority_management_addr)
+                .expect("failed at setting up the `roleManager` contract");
+            let old_instance = self.role_manage.as_ref().unwrap();
+            let roles = old_instance.list_roles();
+            for role in roles.iter() {
+                new_instance.add_role(role.clone()).expect("failed at migrating roles");
+                for privilege in old_instance.list_role_privileges(role.clone()) {
+                    // privileges deleted from authority_management are dropped here
+                    let _ = new_instance.role_insert_privilege(role.clone(), privilege);
+                }
+            }
+            for role in roles.iter() {
+                for parent in old_instance.get_role_parents(role.clone()) {
+                    new_instance.add_role_parent(role.clone(), parent).expect("failed at migrating roles");
+                }
+            }
+            let block = self.env().block_number();
+            for (user, grants) in old_instance.list_user_roles() {
+                for grant in grants {
+                    match grant.expire_block {
+                        None => {
+                            new_instance.add_user_role(user, grant.role).expect("failed at migrating user roles");
+                        }
+                        Some(expire_block) if expire_block > block => {
+                            new_instance.add_temporary_user_role(user, grant.role, expire_block)
+                                .expect("failed at migrating user roles");
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            self.role_manage = Some(new_instance);
+            self.role_manage_addr = new_addr;
+            self.record_module_version(String::from(ROLE_MANAGE), code_hash, new_addr);
+            self.register_module_route(String::from(ROLE_MANAGE), new_addr, code_hash);
+            new_addr
+        }
+        /// Deploy a new version of authority_management and move all privileges into it
+        /// version:Random numbers are used to instantiate the contract
+        /// code_hash:the hash of the new authority_management contract
+        /// # Panics
+        /// Only the owner can be called after the kernel has been initialized
+        #[ink(message)]
+        pub fn upgrade_authority_management(&mut self, version: u32, code_hash: Hash) -> AccountId {
+            self.only_owner(Self::env().caller());
+            assert_eq!(self.init, true);
+            let new_addr = self.instantiate_authority_management(version.to_le_bytes(), code_hash);
+            let mut new_instance: AuthorityManagement = ink_env::call::FromAccountId::from_account_id(new_addr);
+            let old_instance = self.authority_management.as_ref().unwrap();
+            for privilege in old_instance.list_privileges() {
+                // older versions allowed the same name more than once
+                if new_instance.exists_privilege(privilege.clone()) {
+                    continue;
+                }
+                let info = old_instance.query_privilege_by_name(privilege.clone());
+                let (description, module) = match info {
+                    Some(info) => (info.description, info.module),
+                    None => (String::default(), String::default()),
+                };
+                new_instance.add_module_privilege(privilege, description, module)
+                    .expect("failed at migrating privileges");
+            }
+            self.authority_management = Some(new_instance);
+            self.authority_management_addr = new_addr;
+            self.role_manage.as_mut().unwrap().set_authority_management(new_addr)
+                .expect("failed at setting up the `roleManager` contract");
+            self.record_module_version(String::from(AUTHORITY_MANAGEMENT), code_hash, new_addr);
+            self.register_module_route(String::from(AUTHORITY_MANAGEMENT), new_addr, code_hash);
+            new_addr
+        }
+        /// Deploy a new version of route_manage and move all routes into it.
+        /// Every route is replayed version by version, so versions can still be rolled back to,
+        /// the replayed versions are recorded at the block of the upgrade.
+        /// The route_manage route of the old registry is pointed to the new one, contracts that
+        /// stored the address of the old registry, e.g. erc20_factory, must be pointed to the new address
+        /// version:Random numbers are used to instantiate the contract
+        /// code_hash:the hash of the new route contract
+        /// # Panics
+        /// Only the owner can be called after the kernel has been initialized
+        #[ink(message)]
+        pub fn upgrade_route_manage(&mut self, version: u32, code_hash: Hash) -> AccountId {
+            self.only_owner(Self::env().caller());
+            assert_eq!(self.init, true);
+            let new_addr = self.instantiate_route_manage(version.to_le_bytes(), code_hash);
+            let mut new_instance: RouteManage = ink_env::call::FromAccountId::from_account_id(new_addr);
+            let old_instance = self.route_manage.as_ref().unwrap();
+            for (name, addr) in old_instance.list_route() {
+                let history = old_instance.route_history(name.clone());
+                match history.split_first() {
+                    Some((first, rest)) => {
+                        new_instance.add_route(name.clone(), first.value);
+                        for route_version in rest {
+                            new_instance.change_route(name.clone(), route_version.value);
+                        }
+                    }
+                    None => {
+                        new_instance.add_route(name.clone(), addr);
+                    }
+                }
+                if let Some(info) = old_instance.get_route_info(name.clone()) {
+                    new_instance.set_route_info(name, info.code_hash, info.interface_id, info.description);
+                }
+            }
+            // Still the old registry, so its readers can find the new one
+            self.register_module_route(String::from(ROUTE_MANAGE), new_addr, code_hash);
+            self.route_manage = Some(new_instance);
+            self.route_manage_addr = new_addr;
+            self.record_module_version(String::from(ROUTE_MANAGE), code_hash, new_addr);
+            self.register_module_route(String::from(ROUTE_MANAGE), new_addr, code_hash);
+            new_addr
+        }
+        /// Show all versions of a managed contract, the last one is running
+        /// name:role_manage, authority_management or route_manage
+        #[ink(message)]
+        pub fn get_module_versions(&self, name: String) -> Vec<ModuleVersion> {
+            self.module_versions.get(&name).cloned().unwrap_or(Vec::new())
+        }
+
+        fn apply_operation(&mut self, operation: AdminOperation) -> core::result::Result<(), String> {
+            if !self.init {
+                return Err(String::from("kernel is not initialized"));
+            }
+            match operation {
+                AdminOperation::AddRole(name) =>
+                    self.role_manage.as_mut().unwrap().add_role(name).map_err(|error| format!("{:?}", error)),
+                AdminOperation::RemoveRole(name) =>
+                    self.role_manage.as_mut().unwrap().remove_role(name).map_err(|error| format!("{:?}", error)),
+                AdminOperation::RoleInsertPrivilege(name, privilege) =>
+                    self.role_manage.as_mut().unwrap().role_insert_privilege(name, privilege).map_err(|error| format!("{:?}", error)),
+                AdminOperation::RoleRemovePrivilege(name, privilege) =>
+                    self.role_manage.as_mut().unwrap().role_remove_privilege(name, privilege).map_err(|error| format!("{:?}", error)),
+                AdminOperation::AddRoleParent(name, parent) =>
+                    self.role_manage.as_mut().unwrap().add_role_parent(name, parent).map_err(|error| format!("{:?}", error)),
+                AdminOperation::RemoveRoleParent(name, parent) =>
+                    self.role_manage.as_mut().unwrap().remove_role_parent(name, parent).map_err(|error| format!("{:?}", error)),
+                AdminOperation::AddUserRole(user, role) =>
+                    self.role_manage.as_mut().unwrap().add_user_role(user, role).map_err(|error| format!("{:?}", error)),
+                AdminOperation::AddTemporaryUserRole(user, role, expire_block) =>
+                    self.role_manage.as_mut().unwrap().add_temporary_user_role(user, role, expire_block).map_err(|error| format!("{:?}", error)),
+                AdminOperation::RemoveUserRole(user, role) =>
+                    self.role_manage.as_mut().unwrap().remove_user_role(user, role).map_err(|error| format!("{:?}", error)),
+                AdminOperation::AddPrivilege(name) =>
+                    self.authority_management.as_mut().unwrap().add_privilege(name).map_err(|error| format!("{:?}", error)),
+                AdminOperation::AddModulePrivilege(name, description, module) =>
+                    self.authority_management.as_mut().unwrap().add_module_privilege(name, description, module).map_err(|error| format!("{:?}", error)),
+                AdminOperation::RemovePrivilege(name) =>
+                    self.delete_privilege(name).map_err(|error| format!("{:?}", error)),
+                AdminOperation::AddRoute(name, value) =>
+                    Self::route_result(self.route_manage.as_mut().unwrap().add_route(name, value)),
+                AdminOperation::ChangeRoute(name, value) =>
+                    Self::route_result(self.route_manage.as_mut().unwrap().change_route(name, value)),
+                AdminOperation::RollbackRoute(name, version) =>
+                    Self::route_result(self.route_manage.as_mut().unwrap().rollback_route(name, version)),
+                AdminOperation::SetRouteInfo(name, code_hash, interface_id, description) =>
+                    Self::route_result(self.route_manage.as_mut().unwrap().set_route_info(name, code_hash, interface_id, description)),
+                AdminOperation::RemoveRoute(name) =>
+                    Self::route_result(self.route_manage.as_mut().unwrap().remove_route(name)),
+            }
+        }
+
+        fn route_result(result: bool) -> core::result::Result<(), String> {
+            if result { Ok(()) } else { Err(String::from("route_manage refused the operation")) }
+        }
+
+        fn delete_privilege(&mut self, name: String) -> authority_management::Result<()> {
+            self.authority_management.as_mut().unwrap().remove_privilege(name.clone())?;
+            let revoked = self.role_manage.as_mut().unwrap().remove_privilege_from_roles(name);
+            assert!(revoked.is_ok(), "failed to remove the privilege from roles");
+            Ok(())
+        }
+
+        fn instantiate_role_manage(&self, salt: [u8; 4], code_hash: Hash) -> AccountId {
             let role_manage = RoleManage::new()
                 .endowment(DAO_INIT_BALANCE)
-                .code_hash(role_code_hash)
+                .code_hash(code_hash)
                 .salt_bytes(salt)
                 .params();
             let init_role_result = ink_env::instantiate_contract(&role_manage);
-            let role_manage_addr = init_role_result.expect("failed at instantiating the `roleManager` contract");
-            let role_contract_instance = ink_env::call::FromAccountId::from_account_id(role_manage_addr);
-            self.role_manage = Some(role_contract_instance);
-            self.role_manage_addr = role_manage_addr;
+            init_role_result.expect("failed at instantiating the `roleManager` contract")
+        }
 
+        fn instantiate_authority_management(&self, salt: [u8; 4], code_hash: Hash) -> AccountId {
             let authority_management = AuthorityManagement::new()
                 .endowment(DAO_INIT_BALANCE)
-                .code_hash(privilege_code_hash)
+                .code_hash(code_hash)
                 .salt_bytes(salt)
                 .params();
             let init_authority_result = ink_env::instantiate_contract(&authority_management);
-            let authority_management_addr = init_authority_result.expect(
+            init_authority_result.expect(
                 "failed at instantiating the `authority_management` contract"
-            );
-            let authority_contract_instance = ink_env::call::FromAccountId::from_account_id(authority_management_addr);
-            self.authority_management = Some(authority_contract_instance);
-            self.authority_management_addr = authority_management_addr;
+            )
+        }
 
+        fn instantiate_route_manage(&self, salt: [u8; 4], code_hash: Hash) -> AccountId {
             let route_manage = RouteManage::new()
                 .endowment(DAO_INIT_BALANCE)
-                .code_hash(route_code_hash)
+                .code_hash(code_hash)
                 .salt_bytes(salt)
                 .params();
             let init_route_result = ink_env::instantiate_contract(&route_manage);
-            let route_manage_addr = init_route_result.expect(
+            init_route_result.expect(
                 "failed at instantiating the `route_manage` contract"
+            )
+        }
+
+        fn record_module_version(&mut self, name: String, code_hash: Hash, addr: AccountId) {
+            let block = self.env().block_number();
+            let versions = self.module_versions.entry(name.clone()).or_insert(Vec::new());
+            let version = versions.len() as u32 + 1;
+            versions.push(ModuleVersion { version, code_hash, addr, block });
+            self.env().emit_event(ModuleUpgraded {
+                module: name,
+                version,
+                code_hash,
+                addr,
+            });
+        }
+
+        fn register_route(&mut self, name: String, addr: AccountId) {
+            let route_manage = self.route_manage.as_mut().unwrap();
+            if route_manage.query_route_by_name(name.clone()) == AccountId::default() {
+                route_manage.add_route(name, addr);
+            } else {
+                route_manage.change_route(name, addr);
+            }
+        }
+
+        fn register_module_route(&mut self, name: String, addr: AccountId, code_hash: Hash) {
+            self.register_route(name.clone(), addr);
+            self.route_manage.as_mut().unwrap().set_route_info(
+                name.clone(),
+                code_hash,
+                name,
+                String::from("managed by kernel"),
             );
-            let route_contract_instance = ink_env::call::FromAccountId::from_account_id(route_manage_addr);
-            self.route_manage = Some(route_contract_instance);
-            self.route_manage_addr = route_manage_addr;
-            self.init = true;
-            true
         }
     }
 
@@ -174,5 +731,109 @@ mod kernel {
             let kernel = Kernel::new();
             assert!(kernel.get_role_addr() == AccountId::default());
         }
+
+        #[ink::test]
+        fn module_versions_works() {
+            let mut kernel = Kernel::new();
+            assert!(kernel.get_module_versions(String::from(ROLE_MANAGE)).is_empty());
+            kernel.record_module_version(String::from(ROLE_MANAGE), Hash::from([0x01; 32]), AccountId::from([0x02; 32]));
+            kernel.record_module_version(String::from(ROLE_MANAGE), Hash::from([0x03; 32]), AccountId::from([0x04; 32]));
+            let versions = kernel.get_module_versions(String::from(ROLE_MANAGE));
+            assert_eq!(versions.len(), 2);
+            assert_eq!(versions[1].version, 2);
+            assert!(versions[1].code_hash == Hash::from([0x03; 32]));
+            assert!(versions[1].addr == AccountId::from([0x04; 32]));
+        }
+
+        #[ink::test]
+        fn empty_batch_works() {
+            let mut kernel = Kernel::new();
+            assert!(kernel.batch(Vec::new()));
+            assert_eq!(ink_env::test::recorded_events().count(), 1);
+        }
+
+        #[ink::test]
+        #[should_panic(expected = "admin operation 0 failed: kernel is not initialized")]
+        fn batch_fails_on_failing_operation() {
+            let mut kernel = Kernel::new();
+            kernel.batch(ink_prelude::vec![
+                AdminOperation::AddRole(String::from("member")),
+                AdminOperation::AddPrivilege(String::from("vote")),
+            ]);
+        }
+
+        #[ink::test]
+        #[should_panic]
+        fn batch_fails_for_non_owner() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                    .expect("Cannot get accounts");
+            let mut kernel = Kernel::new();
+            set_caller(accounts.bob);
+            kernel.batch(Vec::new());
+        }
+
+        #[ink::test]
+        fn is_authorized_works() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                    .expect("Cannot get accounts");
+            let kernel = Kernel::new();
+            assert!(kernel.is_authorized(accounts.alice, String::from("test")));
+            assert!(!kernel.is_authorized(accounts.bob, String::from("test")));
+            assert!(!kernel.has_privilege(accounts.alice, String::from("test")));
+        }
+
+        fn set_caller(caller: AccountId) {
+            let callee = ink_env::account_id::<ink_env::DefaultEnvironment>()
+                .unwrap_or([0x0; 32].into());
+            ink_env::test::push_execution_context::<ink_env::DefaultEnvironment>(
+                caller,
+                callee,
+                1000000,
+                1000000,
+                ink_env::test::CallData::new(ink_env::call::Selector::new([0x00; 4])),
+            );
+        }
+
+        #[ink::test]
+        fn transfer_owner_works() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                    .expect("Cannot get accounts");
+            let mut kernel = Kernel::new();
+            assert!(kernel.propose_owner(accounts.bob));
+            assert!(kernel.get_owner() == accounts.alice);
+            assert!(kernel.get_pending_owner() == Some(accounts.bob));
+            set_caller(accounts.bob);
+            assert!(kernel.accept_owner());
+            assert!(kernel.get_owner() == accounts.bob);
+            assert!(kernel.get_pending_owner() == None);
+            assert_eq!(ink_env::test::recorded_events().count(), 2);
+        }
+
+        #[ink::test]
+        #[should_panic]
+        fn accept_owner_fails_without_proposal() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                    .expect("Cannot get accounts");
+            let mut kernel = Kernel::new();
+            set_caller(accounts.bob);
+            kernel.accept_owner();
+        }
+
+        #[ink::test]
+        #[should_panic]
+        fn renounce_owner_locks_kernel() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                    .expect("Cannot get accounts");
+            let mut kernel = Kernel::new();
+            assert!(kernel.renounce_owner());
+            assert!(kernel.get_owner() == AccountId::default());
+            assert!(!kernel.is_authorized(AccountId::default(), String::from("test")));
+            kernel.propose_owner(accounts.bob);
+        }
     }
 }
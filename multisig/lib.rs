@@ -2,14 +2,22 @@
 extern crate alloc;
 pub use self::multisig::{
     Multisig,
+    CallTransaction,
+    TransactionKind,
+    TransactionStatus,
 };
 use ink_lang as ink;
 #[allow(unused_imports)]
 #[allow(unused_must_use)]
 #[ink::contract]
 mod multisig {
+    use ink_env::call::{
+        build_call,
+        ExecutionInput,
+    };
     use ink_prelude::vec::Vec;
     use ink_prelude::collections::BTreeMap;
+    use erc20::Erc20;
     use ink_storage::{
         collections::{
             HashMap as StorageHashMap,
@@ -19,14 +27,132 @@ mod multisig {
             SpreadLayout,
         },
     };
+    use scale::Output;
+
+    struct CallInput<'a>(&'a [u8]);
+
+    impl<'a> scale::Encode for CallInput<'a> {
+        fn encode_to<T: Output + ?Sized>(&self, dest: &mut T) {
+            dest.write(self.0);
+        }
+    }
+
+    /// A call to any contract, made by the multisig once enough managers signed
+    #[derive(scale::Encode, scale::Decode, Clone, SpreadLayout, PackedLayout)]
+    #[cfg_attr(
+    feature = "std",
+    derive(scale_info::TypeInfo, ink_storage::traits::StorageLayout)
+    )]
+    #[derive(Debug)]
+    pub struct CallTransaction {
+        /// The `AccountId` of the contract that is called in this transaction.
+        callee: AccountId,
+        /// The selector bytes that identifies the function of the callee that should be called.
+        selector: [u8; 4],
+        /// The SCALE encoded parameters that are passed to the called function.
+        input: Vec<u8>,
+        /// The amount of chain balance that is transferred to the callee.
+        transferred_value: Balance,
+        /// Gas limit for the execution of the call.
+        gas_limit: u64,
+    }
+
+    /// What a transaction does once enough managers signed
+    #[derive(scale::Encode, scale::Decode, Clone, SpreadLayout, PackedLayout)]
+    #[cfg_attr(
+    feature = "std",
+    derive(scale_info::TypeInfo, ink_storage::traits::StorageLayout)
+    )]
+    #[derive(Debug)]
+    pub enum TransactionKind {
+        /// Transfer native balance to an address
+        Transfer {
+            to: AccountId,
+            amount: u64,
+        },
+        /// Call a contract
+        Call(CallTransaction),
+        /// Transfer ERC-20 tokens of the multisig to an address
+        Erc20Transfer {
+            token: AccountId,
+            to: AccountId,
+            amount: Balance,
+        },
+        /// Let a spender use ERC-20 tokens of the multisig
+        Erc20Approve {
+            token: AccountId,
+            spender: AccountId,
+            amount: Balance,
+        },
+        /// Add a manager with a signing weight
+        AddManager {
+            manager: AccountId,
+            weight: i32,
+        },
+        /// Remove a manager
+        RemoveManager {
+            manager: AccountId,
+        },
+        /// Replace a manager by another account with the same signing weight
+        ReplaceManager {
+            old_manager: AccountId,
+            new_manager: AccountId,
+        },
+        /// Change the signing weight of a manager
+        ChangeManagerWeight {
+            manager: AccountId,
+            weight: i32,
+        },
+        /// Change the minimum summed weight of signatures
+        ChangeMinSignCount {
+            min_sign_count: i32,
+        },
+        /// Cancel another pending or queued transaction, executed as soon as
+        /// enough managers signed
+        CancelTransaction {
+            transaction_id: u64,
+        },
+        /// Change the number of blocks a queued transaction waits before it can be executed
+        ChangeExecutionDelay {
+            execution_delay: u32,
+        },
+        /// Show an ERC-20 token in the treasury
+        TrackToken {
+            token: AccountId,
+        },
+    }
+
+    /// The status of a transaction
+    #[derive(scale::Encode, scale::Decode, Clone, Copy, PartialEq, Eq, SpreadLayout, PackedLayout)]
+    #[cfg_attr(
+    feature = "std",
+    derive(scale_info::TypeInfo, ink_storage::traits::StorageLayout)
+    )]
+    #[derive(Debug)]
+    pub enum TransactionStatus {
+        /// Waiting for signatures
+        Pending,
+        /// Signed by enough managers, waiting for the execution delay
+        Queued,
+        /// Signed by enough managers and executed
+        Executed,
+        /// Cancelled by the proposer or by enough managers
+        Cancelled,
+        /// Not signed by enough managers before the expire block
+        Expired,
+        /// Signed by enough managers but the execution failed
+        Failed,
+    }
 
     /// Execution details
     /// id:the id of multisig
     /// status:the status of multisig
-    /// to:Transfer token to an address
-    /// amount:Number of transfers
+    /// kind:what the transaction does
+    /// proposer:the manager who created the transaction
+    /// expire_block:the transaction can not be executed from this block on
+    /// executable_block:the block from which a queued transaction can be executed
     /// signature_count:Number of signatures
-    /// signatures:Details of signature
+    /// signatures:the signers and their signing weight when they signed
     #[derive(scale::Encode, scale::Decode, Clone, SpreadLayout, PackedLayout)]
     #[cfg_attr(
     feature = "std",
@@ -35,9 +161,11 @@ mod multisig {
     #[derive(Debug)]
     pub struct Transaction {
         id:u64,
-        status: bool,
-        to: AccountId,
-        amount: u64,
+        status: TransactionStatus,
+        kind: TransactionKind,
+        proposer: AccountId,
+        expire_block: Option<u32>,
+        executable_block: Option<u32>,
         signature_count: i32,
         signatures: BTreeMap<AccountId, i32>,
     }
@@ -45,9 +173,11 @@ mod multisig {
     /// Sign multiple transfer contracts
     /// owner : the creator of the contract
     /// transaction_idx : the index of transaction
-    /// manager:Administrator who needs to sign
+    /// manager:Administrator who needs to sign and its signing weight, changed only by multi sign transactions
     /// transactions:Execution details
-    /// min_sign_count : Minimum number of signatures
+    /// min_sign_count : Minimum summed weight of signatures
+    /// tracked_tokens : ERC-20 tokens shown in the treasury
+    /// execution_delay : the number of blocks a queued transaction waits before it can be executed
     #[ink(storage)]
     pub struct Multisig {
         owner: AccountId,
@@ -56,17 +186,31 @@ mod multisig {
         transactions: StorageHashMap<u64, Transaction>,
         info: StorageHashMap<u64, AccountId>,
         min_sign_count: i32,
+        tracked_tokens: Vec<AccountId>,
+        execution_delay: u32,
     }
 
 
 
     impl Multisig {
+        /// Every manager signs with a weight of 1
         #[ink(constructor)]
         pub fn new(owners: Vec<AccountId>,min_sign_count: i32,) -> Self {
+            Self::new_weighted(owners.into_iter().map(|addr| (addr, 1)).collect(), min_sign_count)
+        }
+
+        /// Every manager signs with its own weight
+        /// owners:the managers and their signing weights
+        /// min_sign_count:the minimum summed weight of signatures
+        #[ink(constructor)]
+        pub fn new_weighted(owners: Vec<(AccountId, i32)>,min_sign_count: i32,) -> Self {
             let mut map: StorageHashMap<AccountId, i32> = StorageHashMap::new();
-            for addr in &owners{
-                    map.insert(*addr,1);
+            for (addr, weight) in &owners{
+                    assert!(*weight > 0, "weight must be positive");
+                    map.insert(*addr,*weight);
                 }
+            let total_weight: i64 = map.values().map(|weight| *weight as i64).sum();
+            assert!(min_sign_count > 0 && min_sign_count as i64 <= total_weight, "min sign count can not be reached");
             Self {
                 owner: Self::env().caller(),
                 transaction_idx: 0,
@@ -74,72 +218,276 @@ mod multisig {
                 transactions: StorageHashMap::new(),
                 info: StorageHashMap::new(),
                 min_sign_count,
+                tracked_tokens: Vec::new(),
+                execution_delay: 0,
             }
         }
 
         /// Create a multi sign transaction
         /// to:Transfer token to an address
         /// amount:The number of transfer
+        /// expire_block:the transaction can not be executed from this block on
         #[ink(message)]
-        pub fn creat_transfer(&mut self,to: AccountId ,amount: u64) -> bool {
+        pub fn creat_transfer(&mut self,to: AccountId ,amount: u64,expire_block: Option<u32>) -> bool {
             self.ensure_caller_is_manager();
             assert_eq!(self.env().balance() >= amount.into(), true);
-            self.transactions.insert(self.transaction_idx,
-                Transaction{
-                    id:self.transaction_idx,
-                    status: false,
-                    to,
-                    amount,
-                    signature_count: 0,
-                    signatures: BTreeMap::new(),
-                }
-            );
-            self.transaction_idx += 1;
+            self.insert_transaction(TransactionKind::Transfer { to, amount },expire_block);
+            true
+        }
+        /// Create a multi sign transaction calling a contract
+        /// call:the callee, selector, encoded input, transferred value and gas limit of the call
+        /// expire_block:the transaction can not be executed from this block on
+        #[ink(message)]
+        pub fn creat_call(&mut self,call: CallTransaction,expire_block: Option<u32>) -> bool {
+            self.ensure_caller_is_manager();
+            assert_eq!(self.env().balance() >= call.transferred_value, true);
+            self.insert_transaction(TransactionKind::Call(call),expire_block);
+            true
+        }
+        /// Create a multi sign transaction transferring ERC-20 tokens,
+        /// the token is tracked in the treasury once the transfer succeeded
+        /// token:the address of the ERC-20 token
+        /// to:Transfer token to an address
+        /// amount:The number of transfer
+        /// expire_block:the transaction can not be executed from this block on
+        #[ink(message)]
+        pub fn creat_erc20_transfer(&mut self,token: AccountId,to: AccountId,amount: Balance,expire_block: Option<u32>) -> bool {
+            self.ensure_caller_is_manager();
+            self.insert_transaction(TransactionKind::Erc20Transfer { token, to, amount },expire_block);
+            true
+        }
+        /// Create a multi sign transaction approving a spender of ERC-20 tokens,
+        /// the token is tracked in the treasury once the approval succeeded
+        /// token:the address of the ERC-20 token
+        /// spender:the address allowed to spend the tokens
+        /// amount:the number of tokens the spender may use
+        /// expire_block:the transaction can not be executed from this block on
+        #[ink(message)]
+        pub fn creat_erc20_approve(&mut self,token: AccountId,spender: AccountId,amount: Balance,expire_block: Option<u32>) -> bool {
+            self.ensure_caller_is_manager();
+            self.insert_transaction(TransactionKind::Erc20Approve { token, spender, amount },expire_block);
+            true
+        }
+        /// Create a multi sign transaction showing an ERC-20 token in the treasury,
+        /// every tracked token is called by get_treasury so it must be approved by enough managers
+        /// token:the address of the ERC-20 token
+        /// expire_block:the transaction can not be executed from this block on
+        #[ink(message)]
+        pub fn track_token(&mut self,token: AccountId,expire_block: Option<u32>) -> bool {
+            self.ensure_caller_is_manager();
+            self.insert_transaction(TransactionKind::TrackToken { token },expire_block);
+            true
+        }
+        /// Stop showing an ERC-20 token in the treasury
+        /// token:the address of the ERC-20 token
+        #[ink(message)]
+        pub fn untrack_token(&mut self,token: AccountId) -> bool {
+            self.ensure_caller_is_manager();
+            self.tracked_tokens.retain(|tracked_token| *tracked_token != token);
             true
         }
-        /// Sign a transaction
+        /// Get the ERC-20 tokens shown in the treasury
+        #[ink(message)]
+        pub fn get_tracked_tokens(&self) -> Vec<AccountId> {
+            self.tracked_tokens.clone()
+        }
+        /// Get the balance of the multisig for each tracked ERC-20 token
+        #[ink(message)]
+        pub fn get_treasury(&self) -> Vec<(AccountId, Balance)> {
+            let multisig_addr = self.env().account_id();
+            self.tracked_tokens.iter().map(|token| {
+                let erc20_instance: Erc20 = ink_env::call::FromAccountId::from_account_id(*token);
+                (*token, erc20_instance.balance_of(multisig_addr))
+            }).collect()
+        }
+        /// Sign a transaction, an expired transaction is marked as expired instead
         /// transaction_id:the id of transaction
         #[ink(message)]
         pub fn sign_transaction(&mut self, transaction_id: u64) -> bool {
             self.ensure_caller_is_manager();
             let from = self.env().caller();
+            let weight = self.get_manage_weight(from);
+            let block = self.env().block_number();
             let mut t = self.transactions.get_mut(&transaction_id).unwrap();
-            assert!(t.status == false, "out!");
+            assert!(t.status == TransactionStatus::Pending, "out!");
+            if t.expire_block.map_or(false, |expire_block| block >= expire_block) {
+                t.status = TransactionStatus::Expired;
+                return false;
+            }
             let if_sign = t.signatures.get(&from);
             assert!(if_sign == None, "out!");
-            t.signatures.insert(from, 1);
+            t.signatures.insert(from, weight);
             t.signature_count += 1;
-            let addr = t.to;
-            let num = t.amount;
-            if t.signature_count >= self.min_sign_count {
-                t.status = true;
-                self.env().transfer(addr, num.into());
+            if !self.is_signed_enough(transaction_id) {
+                return true;
+            }
+            let execution_delay = self.execution_delay;
+            let t = self.transactions.get_mut(&transaction_id).unwrap();
+            if let TransactionKind::CancelTransaction { .. } = t.kind {
+                // Cancelling must not wait, the delay exists to react to bad transactions
+                self.run_transaction(transaction_id);
+            } else {
+                t.status = TransactionStatus::Queued;
+                t.executable_block = Some(block.saturating_add(execution_delay));
+            }
+            true
+        }
+        /// Execute a queued transaction once its execution delay has passed.
+        /// A failed execution is recorded in the status of the transaction,
+        /// a transaction no longer signed by enough managers goes back to pending and returns false
+        /// transaction_id:the id of transaction
+        /// # Panics
+        /// The transaction must be queued and past its delay
+        #[ink(message)]
+        pub fn execute(&mut self, transaction_id: u64) -> bool {
+            self.ensure_caller_is_manager();
+            let block = self.env().block_number();
+            let t = self.transactions.get_mut(&transaction_id).unwrap();
+            assert!(t.status == TransactionStatus::Queued, "out!");
+            if t.expire_block.map_or(false, |expire_block| block >= expire_block) {
+                t.status = TransactionStatus::Expired;
+                return false;
+            }
+            if !self.is_signed_enough(transaction_id) {
+                let t = self.transactions.get_mut(&transaction_id).unwrap();
+                t.status = TransactionStatus::Pending;
+                t.executable_block = None;
+                return false;
+            }
+            let executable_block = self.transactions.get(&transaction_id).unwrap().executable_block;
+            assert!(block >= executable_block.unwrap_or(0), "execution delay has not passed");
+            self.run_transaction(transaction_id)
+        }
+        /// Create a multi sign transaction changing the execution delay
+        /// execution_delay:the number of blocks a queued transaction waits before it can be executed
+        /// expire_block:the transaction can not be executed from this block on
+        #[ink(message)]
+        pub fn change_execution_delay(&mut self,execution_delay: u32,expire_block: Option<u32>) -> bool {
+            self.ensure_caller_is_manager();
+            self.insert_transaction(TransactionKind::ChangeExecutionDelay { execution_delay },expire_block);
+            true
+        }
+        /// Get the number of blocks a queued transaction waits before it can be executed
+        #[ink(message)]
+        pub fn get_execution_delay(&self) -> u32 {
+            self.execution_delay
+        }
+        /// Withdraw the signature of the caller from a pending or queued transaction,
+        /// a queued transaction goes back to pending when it is no longer signed by enough managers
+        /// transaction_id:the id of transaction
+        #[ink(message)]
+        pub fn revoke_signature(&mut self, transaction_id: u64) -> bool {
+            let from = self.env().caller();
+            let t = self.transactions.get_mut(&transaction_id).unwrap();
+            assert!(t.status == TransactionStatus::Pending || t.status == TransactionStatus::Queued, "out!");
+            assert!(t.signatures.remove(&from).is_some(), "not signed");
+            t.signature_count -= 1;
+            if !self.is_signed_enough(transaction_id) {
+                let t = self.transactions.get_mut(&transaction_id).unwrap();
+                t.status = TransactionStatus::Pending;
+                t.executable_block = None;
+            }
+            true
+        }
+        /// Cancel a pending or queued transaction. The proposer cancels it at once,
+        /// other managers create a multi sign transaction cancelling it
+        /// transaction_id:the id of transaction
+        #[ink(message)]
+        pub fn cancel_transaction(&mut self, transaction_id: u64) -> bool {
+            self.ensure_caller_is_manager();
+            let caller = self.env().caller();
+            let t = self.transactions.get_mut(&transaction_id).unwrap();
+            assert!(t.status == TransactionStatus::Pending || t.status == TransactionStatus::Queued, "out!");
+            if t.proposer == caller {
+                t.status = TransactionStatus::Cancelled;
+            } else {
+                let expire_block = t.expire_block;
+                self.insert_transaction(TransactionKind::CancelTransaction { transaction_id }, expire_block);
             }
             true
         }
 
-        /// Get a transaction
+        /// Get a transaction, a pending or queued transaction past its expire block is shown as expired
         /// trans_id:the id of transaction
         #[ink(message)]
         pub fn get_transaction(&self,trans_id: u64) -> Transaction {
-            self.transactions.get(&trans_id).unwrap().clone()
+            self.show_expiry(self.transactions.get(&trans_id).unwrap().clone())
+        }
+        /// Get the summed current weight of the managers who signed a transaction,
+        /// signatures of removed managers no longer count
+        /// trans_id:the id of transaction
+        #[ink(message)]
+        pub fn get_signed_weight(&self,trans_id: u64) -> i64 {
+            let t = self.transactions.get(&trans_id).unwrap();
+            t.signatures.keys()
+                .map(|signer| self.manager.get(signer).copied().unwrap_or(0) as i64)
+                .sum()
+        }
+        /// Create a multi sign transaction adding an administrator
+        /// addr:the address of manager
+        /// weight:the signing weight of manager
+        /// expire_block:the transaction can not be executed from this block on
+        #[ink(message)]
+        pub fn add_manage(&mut self,addr: AccountId,weight: i32,expire_block: Option<u32>) -> bool {
+            self.ensure_caller_is_manager();
+            self.insert_transaction(TransactionKind::AddManager { manager: addr, weight },expire_block);
+            true
         }
-        /// Add a multi sign on administrator
+        /// Create a multi sign transaction removing an administrator
         /// addr:the address of manager
+        /// expire_block:the transaction can not be executed from this block on
+        /// # Panics
+        /// The remaining managers must still be able to reach the minimum summed weight of signatures
+        #[ink(message)]
+        pub fn remove_manage(&mut self,addr: AccountId,expire_block: Option<u32>) -> bool {
+            self.ensure_caller_is_manager();
+            self.insert_transaction(TransactionKind::RemoveManager { manager: addr },expire_block);
+            true
+        }
+        /// Create a multi sign transaction replacing an administrator
+        /// old_manager:the address of the manager to replace
+        /// new_manager:the address of the new manager
+        /// expire_block:the transaction can not be executed from this block on
         #[ink(message)]
-        pub fn add_manage(&mut self,addr: AccountId) -> bool {
-            self.ensure_caller_is_owner();
-            self.manager.insert(addr, 1);
+        pub fn replace_manage(&mut self,old_manager: AccountId,new_manager: AccountId,expire_block: Option<u32>) -> bool {
+            self.ensure_caller_is_manager();
+            self.insert_transaction(TransactionKind::ReplaceManager { old_manager, new_manager },expire_block);
             true
         }
-        /// Remove a multi sign on administrator
+        /// Create a multi sign transaction changing the signing weight of an administrator
         /// addr:the address of manager
+        /// weight:the new signing weight of manager
+        /// expire_block:the transaction can not be executed from this block on
+        /// # Panics
+        /// The managers must still be able to reach the minimum summed weight of signatures
         #[ink(message)]
-        pub fn remove_manage(&mut self,addr: AccountId) -> bool {
-            self.ensure_caller_is_owner();
-            self.manager.insert(addr, 0);
+        pub fn change_manage_weight(&mut self,addr: AccountId,weight: i32,expire_block: Option<u32>) -> bool {
+            self.ensure_caller_is_manager();
+            self.insert_transaction(TransactionKind::ChangeManagerWeight { manager: addr, weight },expire_block);
+            true
+        }
+        /// Get the signing weight of an administrator, 0 for other accounts
+        /// addr:the address of manager
+        #[ink(message)]
+        pub fn get_manage_weight(&self,addr: AccountId) -> i32 {
+            self.manager.get(&addr).copied().unwrap_or(0)
+        }
+        /// Create a multi sign transaction changing the minimum summed weight of signatures
+        /// min_sign_count:the new minimum summed weight of signatures
+        /// expire_block:the transaction can not be executed from this block on
+        /// # Panics
+        /// The managers must be able to reach the new minimum summed weight of signatures
+        #[ink(message)]
+        pub fn change_min_sign_count(&mut self,min_sign_count: i32,expire_block: Option<u32>) -> bool {
+            self.ensure_caller_is_manager();
+            self.insert_transaction(TransactionKind::ChangeMinSignCount { min_sign_count },expire_block);
             true
         }
+        /// Get the minimum summed weight of signatures
+        #[ink(message)]
+        pub fn get_min_sign_count(&self) -> i32 {
+            self.min_sign_count
+        }
         /// Get administrator list
         #[ink(message)]
         pub fn get_manage_list(&self) -> Vec<AccountId> {
@@ -152,25 +500,181 @@ mod multisig {
             }
             manager_list
         }
-        /// Get multi sign transaction list
+        /// Get multi sign transaction list, pending or queued transactions past their expire block are shown as expired
         #[ink(message)]
         pub fn get_sign_list(&self) -> Vec<Transaction> {
             let mut sign_list = Vec::new();
             let mut iter = self.transactions.values();
             let mut sign = iter.next();
             while sign.is_some() {
-                sign_list.push(sign.unwrap().clone());
+                sign_list.push(self.show_expiry(sign.unwrap().clone()));
                 sign = iter.next();
             }
             sign_list
         }
-        fn ensure_caller_is_owner(&self) -> bool{
-            self.owner == self.env().caller()
+        /// Mark a pending or queued transaction past its expire block as expired
+        fn show_expiry(&self,mut t: Transaction) -> Transaction {
+            let block = self.env().block_number();
+            let open = t.status == TransactionStatus::Pending || t.status == TransactionStatus::Queued;
+            if open && t.expire_block.map_or(false, |expire_block| block >= expire_block) {
+                t.status = TransactionStatus::Expired;
+            }
+            t
+        }
+        fn insert_transaction(&mut self,kind: TransactionKind,expire_block: Option<u32>) -> u64 {
+            assert!(self.is_valid_kind(&kind), "invalid transaction");
+            let id = self.transaction_idx;
+            self.transactions.insert(id,
+                Transaction{
+                    id,
+                    status: TransactionStatus::Pending,
+                    kind,
+                    proposer: self.env().caller(),
+                    expire_block,
+                    executable_block: None,
+                    signature_count: 0,
+                    signatures: BTreeMap::new(),
+                }
+            );
+            self.transaction_idx += 1;
+            id
+        }
+
+        /// Check the current weights of the signers reach the minimum summed weight of signatures
+        fn is_signed_enough(&self,transaction_id: u64) -> bool {
+            self.get_signed_weight(transaction_id) >= self.min_sign_count as i64
+        }
+
+        fn total_weight(&self) -> i64 {
+            self.manager.values().map(|weight| *weight as i64).sum()
+        }
+
+        /// Execute a transaction and record whether it failed
+        fn run_transaction(&mut self,transaction_id: u64) -> bool {
+            let kind = self.transactions.get(&transaction_id).unwrap().kind.clone();
+            let success = self.execute_kind(kind);
+            self.transactions.get_mut(&transaction_id).unwrap().status =
+                if success { TransactionStatus::Executed } else { TransactionStatus::Failed };
+            success
         }
 
-        fn ensure_caller_is_manager(&self) -> bool {
+        fn insert_tracked_token(&mut self,token: AccountId) {
+            if !self.tracked_tokens.contains(&token) {
+                self.tracked_tokens.push(token);
+            }
+        }
+
+        /// Check a transaction can still be executed, a change of managers
+        /// must keep the minimum summed weight of signatures reachable
+        fn is_valid_kind(&self,kind: &TransactionKind) -> bool {
+            let total_weight = self.total_weight();
+            let min_sign_count = self.min_sign_count as i64;
+            match kind {
+                TransactionKind::AddManager { manager, weight } => {
+                    *weight > 0 && !self.manager.contains_key(manager)
+                }
+                TransactionKind::RemoveManager { manager } => {
+                    self.manager.get(manager).map_or(false, |weight| {
+                        total_weight - *weight as i64 >= min_sign_count
+                    })
+                }
+                TransactionKind::ReplaceManager { old_manager, new_manager } => {
+                    self.manager.contains_key(old_manager) && !self.manager.contains_key(new_manager)
+                }
+                TransactionKind::ChangeManagerWeight { manager, weight } => {
+                    *weight > 0 && self.manager.get(manager).map_or(false, |old_weight| {
+                        total_weight - *old_weight as i64 + *weight as i64 >= min_sign_count
+                    })
+                }
+                TransactionKind::ChangeMinSignCount { min_sign_count } => {
+                    *min_sign_count > 0 && *min_sign_count as i64 <= total_weight
+                }
+                TransactionKind::CancelTransaction { transaction_id } => {
+                    self.transactions.get(transaction_id).map_or(false, |t| {
+                        t.status == TransactionStatus::Pending || t.status == TransactionStatus::Queued
+                    })
+                }
+                _ => true,
+            }
+        }
+
+        /// Execute a transaction, returns false when it failed
+        fn execute_kind(&mut self,kind: TransactionKind) -> bool {
+            if !self.is_valid_kind(&kind) {
+                return false;
+            }
+            match kind {
+                TransactionKind::Transfer { to, amount } => {
+                    self.env().transfer(to, amount.into()).is_ok()
+                }
+                TransactionKind::Call(call) => {
+                    build_call::<<Self as ::ink_lang::ContractEnv>::Env>()
+                        .callee(call.callee)
+                        .gas_limit(call.gas_limit)
+                        .transferred_value(call.transferred_value)
+                        .exec_input(
+                            ExecutionInput::new(call.selector.into())
+                                .push_arg(CallInput(&call.input)),
+                        )
+                        .returns::<()>()
+                        .fire()
+                        .is_ok()
+                }
+                TransactionKind::Erc20Transfer { token, to, amount } => {
+                    let mut erc20_instance: Erc20 = ink_env::call::FromAccountId::from_account_id(token);
+                    let success = erc20_instance.transfer(to, amount);
+                    if success {
+                        self.insert_tracked_token(token);
+                    }
+                    success
+                }
+                TransactionKind::Erc20Approve { token, spender, amount } => {
+                    let mut erc20_instance: Erc20 = ink_env::call::FromAccountId::from_account_id(token);
+                    let success = erc20_instance.approve(spender, amount).is_ok();
+                    if success {
+                        self.insert_tracked_token(token);
+                    }
+                    success
+                }
+                TransactionKind::AddManager { manager, weight } => {
+                    self.manager.insert(manager, weight);
+                    true
+                }
+                TransactionKind::RemoveManager { manager } => {
+                    self.manager.take(&manager);
+                    true
+                }
+                TransactionKind::ReplaceManager { old_manager, new_manager } => {
+                    let weight = self.manager.take(&old_manager).unwrap();
+                    self.manager.insert(new_manager, weight);
+                    true
+                }
+                TransactionKind::ChangeManagerWeight { manager, weight } => {
+                    self.manager.insert(manager, weight);
+                    true
+                }
+                TransactionKind::ChangeMinSignCount { min_sign_count } => {
+                    self.min_sign_count = min_sign_count;
+                    true
+                }
+                TransactionKind::CancelTransaction { transaction_id } => {
+                    self.transactions.get_mut(&transaction_id).unwrap().status = TransactionStatus::Cancelled;
+                    true
+                }
+                TransactionKind::ChangeExecutionDelay { execution_delay } => {
+                    self.execution_delay = execution_delay;
+                    true
+                }
+                TransactionKind::TrackToken { token } => {
+                    self.insert_tracked_token(token);
+                    true
+                }
+            }
+        }
+
+        fn ensure_caller_is_manager(&self) {
             let caller = self.env().caller();
-            self.manager.get(&caller) == Some(&1) || self.owner == caller
+            assert!(self.manager.contains_key(&caller), "not a manager");
         }
 
     }
@@ -197,7 +701,264 @@ mod multisig {
             account_vec.push(accounts.eve);
             let mut multisig = Multisig::new(account_vec,2);
             //multisig.creat_transfer(accounts.bob,2);
-            assert!(multisig.add_manage(accounts.alice) == true);
+            assert!(multisig.add_manage(accounts.charlie,1,None) == true);
+        }
+
+        fn set_caller(caller: AccountId) {
+            let callee = ink_env::account_id::<ink_env::DefaultEnvironment>()
+                .unwrap_or([0x0; 32].into());
+            ink_env::test::push_execution_context::<ink_env::DefaultEnvironment>(
+                caller,
+                callee,
+                1000000,
+                1000000,
+                ink_env::test::CallData::new(ink_env::call::Selector::new([0x00; 4])),
+            );
+        }
+
+        #[ink::test]
+        fn manager_changes_work() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                    .expect("Cannot get accounts");
+            let mut multisig = Multisig::new(ink_prelude::vec![accounts.alice, accounts.bob],2);
+            multisig.add_manage(accounts.charlie,1,None);
+            multisig.sign_transaction(0);
+            assert_eq!(multisig.get_manage_list().len(), 2);
+            set_caller(accounts.bob);
+            multisig.sign_transaction(0);
+            assert!(multisig.get_transaction(0).status == TransactionStatus::Queued);
+            multisig.execute(0);
+            assert_eq!(multisig.get_manage_list().len(), 3);
+            multisig.change_min_sign_count(3,None);
+            multisig.sign_transaction(1);
+            set_caller(accounts.charlie);
+            multisig.sign_transaction(1);
+            multisig.execute(1);
+            assert_eq!(multisig.get_min_sign_count(), 3);
+            multisig.replace_manage(accounts.bob, accounts.django,None);
+            multisig.sign_transaction(2);
+            set_caller(accounts.alice);
+            multisig.sign_transaction(2);
+            set_caller(accounts.bob);
+            multisig.sign_transaction(2);
+            multisig.execute(2);
+            assert!(multisig.get_transaction(2).status == TransactionStatus::Executed);
+            assert!(multisig.get_manage_list().contains(&accounts.django));
+            assert!(!multisig.get_manage_list().contains(&accounts.bob));
+        }
+
+        #[ink::test]
+        #[should_panic]
+        fn remove_manage_fails_below_min_sign_count() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                    .expect("Cannot get accounts");
+            let mut multisig = Multisig::new(ink_prelude::vec![accounts.alice, accounts.bob],2);
+            multisig.remove_manage(accounts.bob,None);
+        }
+
+        #[ink::test]
+        #[should_panic]
+        fn add_manage_fails_for_non_manager() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                    .expect("Cannot get accounts");
+            let mut multisig = Multisig::new(ink_prelude::vec![accounts.bob, accounts.charlie],2);
+            multisig.add_manage(accounts.eve,1,None);
+        }
+
+        #[ink::test]
+        fn creat_call_works() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                    .expect("Cannot get accounts");
+            let mut multisig = Multisig::new(ink_prelude::vec![accounts.alice, accounts.bob],2);
+            multisig.creat_call(CallTransaction {
+                callee: accounts.charlie,
+                selector: [1, 2, 3, 4],
+                input: ink_prelude::vec![1],
+                transferred_value: 0,
+                gas_limit: 1000000,
+            },None);
+            multisig.sign_transaction(0);
+            let transaction = multisig.get_transaction(0);
+            assert!(transaction.status == TransactionStatus::Pending);
+            assert_eq!(transaction.signature_count, 1);
+            match transaction.kind {
+                TransactionKind::Call(call) => assert_eq!(call.selector, [1, 2, 3, 4]),
+                _ => panic!("expected a call transaction"),
+            }
+        }
+
+        #[ink::test]
+        fn track_token_works() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                    .expect("Cannot get accounts");
+            let token = AccountId::from([0x01; 32]);
+            let mut multisig = Multisig::new(ink_prelude::vec![accounts.alice, accounts.bob],2);
+            multisig.creat_erc20_transfer(token,accounts.charlie,100,None);
+            multisig.creat_erc20_approve(token,accounts.charlie,100,None);
+            multisig.track_token(token,None);
+            assert!(multisig.get_tracked_tokens().is_empty());
+            multisig.sign_transaction(2);
+            set_caller(accounts.bob);
+            multisig.sign_transaction(2);
+            multisig.execute(2);
+            assert!(multisig.get_tracked_tokens() == ink_prelude::vec![token]);
+            multisig.untrack_token(token);
+            assert!(multisig.get_tracked_tokens().is_empty());
+        }
+
+        #[ink::test]
+        fn revoke_and_cancel_work() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                    .expect("Cannot get accounts");
+            let mut multisig = Multisig::new(ink_prelude::vec![accounts.alice, accounts.bob, accounts.charlie],2);
+            multisig.add_manage(accounts.django,1,None);
+            multisig.sign_transaction(0);
+            multisig.revoke_signature(0);
+            assert_eq!(multisig.get_transaction(0).signature_count, 0);
+            multisig.cancel_transaction(0);
+            assert!(multisig.get_transaction(0).status == TransactionStatus::Cancelled);
+            multisig.add_manage(accounts.django,1,None);
+            set_caller(accounts.bob);
+            multisig.cancel_transaction(1);
+            assert!(multisig.get_transaction(1).status == TransactionStatus::Pending);
+            multisig.sign_transaction(2);
+            set_caller(accounts.charlie);
+            multisig.sign_transaction(2);
+            assert!(multisig.get_transaction(2).status == TransactionStatus::Executed);
+            assert!(multisig.get_transaction(1).status == TransactionStatus::Cancelled);
+        }
+
+        #[ink::test]
+        fn execution_waits_for_delay() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                    .expect("Cannot get accounts");
+            let mut multisig = Multisig::new(ink_prelude::vec![accounts.alice, accounts.bob],2);
+            multisig.change_execution_delay(2,None);
+            multisig.sign_transaction(0);
+            set_caller(accounts.bob);
+            multisig.sign_transaction(0);
+            multisig.execute(0);
+            assert_eq!(multisig.get_execution_delay(), 2);
+            multisig.add_manage(accounts.charlie,1,None);
+            multisig.sign_transaction(1);
+            set_caller(accounts.alice);
+            multisig.sign_transaction(1);
+            multisig.revoke_signature(1);
+            assert!(multisig.get_transaction(1).status == TransactionStatus::Pending);
+            multisig.sign_transaction(1);
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>().expect("Cannot advance block");
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>().expect("Cannot advance block");
+            multisig.execute(1);
+            assert!(multisig.get_transaction(1).status == TransactionStatus::Executed);
+        }
+
+        #[ink::test]
+        fn execute_returns_to_pending_when_threshold_raised() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                    .expect("Cannot get accounts");
+            let mut multisig = Multisig::new(ink_prelude::vec![accounts.alice, accounts.bob, accounts.charlie],2);
+            multisig.add_manage(accounts.django,1,None);
+            multisig.change_min_sign_count(3,None);
+            multisig.sign_transaction(0);
+            multisig.sign_transaction(1);
+            set_caller(accounts.bob);
+            multisig.sign_transaction(0);
+            multisig.sign_transaction(1);
+            assert!(multisig.get_transaction(0).status == TransactionStatus::Queued);
+            assert!(multisig.execute(1));
+            assert!(!multisig.execute(0));
+            let transaction = multisig.get_transaction(0);
+            assert!(transaction.status == TransactionStatus::Pending);
+            assert!(transaction.executable_block.is_none());
+            set_caller(accounts.charlie);
+            multisig.sign_transaction(0);
+            assert!(multisig.execute(0));
+            assert_eq!(multisig.get_manage_weight(accounts.django), 1);
+        }
+
+        #[ink::test]
+        #[should_panic]
+        fn execute_fails_before_delay() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                    .expect("Cannot get accounts");
+            let mut multisig = Multisig::new(ink_prelude::vec![accounts.alice, accounts.bob],2);
+            multisig.change_execution_delay(2,None);
+            multisig.sign_transaction(0);
+            set_caller(accounts.bob);
+            multisig.sign_transaction(0);
+            multisig.execute(0);
+            multisig.add_manage(accounts.charlie,1,None);
+            multisig.sign_transaction(1);
+            set_caller(accounts.alice);
+            multisig.sign_transaction(1);
+            multisig.execute(1);
+        }
+
+        #[ink::test]
+        fn weighted_signers_work() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                    .expect("Cannot get accounts");
+            let mut multisig = Multisig::new_weighted(
+                ink_prelude::vec![(accounts.alice, 3), (accounts.bob, 1), (accounts.charlie, 1)],
+                3
+            );
+            multisig.change_manage_weight(accounts.bob,2,None);
+            set_caller(accounts.bob);
+            multisig.sign_transaction(0);
+            set_caller(accounts.charlie);
+            multisig.sign_transaction(0);
+            assert!(multisig.get_transaction(0).status == TransactionStatus::Pending);
+            assert_eq!(multisig.get_signed_weight(0), 2);
+            assert_eq!(multisig.get_transaction(0).signatures.get(&accounts.charlie), Some(&1));
+            set_caller(accounts.alice);
+            multisig.sign_transaction(0);
+            multisig.execute(0);
+            assert_eq!(multisig.get_manage_weight(accounts.bob), 2);
+            multisig.replace_manage(accounts.alice,accounts.django,None);
+            multisig.sign_transaction(1);
+            assert!(multisig.get_transaction(1).status == TransactionStatus::Queued);
+            multisig.execute(1);
+            assert_eq!(multisig.get_manage_weight(accounts.django), 3);
+            assert_eq!(multisig.get_manage_weight(accounts.alice), 0);
+        }
+
+        #[ink::test]
+        #[should_panic]
+        fn change_manage_weight_fails_below_min_sign_count() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                    .expect("Cannot get accounts");
+            let mut multisig = Multisig::new_weighted(
+                ink_prelude::vec![(accounts.alice, 3), (accounts.bob, 1)],
+                4
+            );
+            multisig.change_manage_weight(accounts.alice,2,None);
+        }
+
+        #[ink::test]
+        fn expired_transaction_can_not_be_signed() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                    .expect("Cannot get accounts");
+            let mut multisig = Multisig::new(ink_prelude::vec![accounts.alice, accounts.bob],2);
+            multisig.add_manage(accounts.charlie,1,Some(1));
+            multisig.sign_transaction(0);
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>().expect("Cannot advance block");
+            assert!(multisig.get_transaction(0).status == TransactionStatus::Expired);
+            assert!(multisig.get_sign_list()[0].status == TransactionStatus::Expired);
+            set_caller(accounts.bob);
+            assert!(!multisig.sign_transaction(0));
+            assert_eq!(multisig.get_manage_list().len(), 2);
         }
     }
 }
@@ -2,6 +2,9 @@
 extern crate alloc;
 pub use self::role_manage::{
     RoleManage,
+    RoleGrant,
+    Error,
+    Result,
     // RoleManageRef,
 };
 use ink_lang as ink;
@@ -11,23 +14,131 @@ use ink_lang as ink;
 mod role_manage {
     use alloc::string::String;
     use ink_prelude::vec::Vec;
-    use ink_storage::{collections::HashMap as StorageHashMap, };
+    use ink_prelude::collections::BTreeMap;
+    use authority_management::AuthorityManagement;
+    use ink_storage::{
+        collections::HashMap as StorageHashMap,
+        traits::{
+            PackedLayout,
+            SpreadLayout,
+        }
+    };
+
+    /// A role given to a user
+    /// role:the name of role
+    /// expire_block:the grant is ignored from this block on, None means it never expires
+    #[derive(scale::Encode, scale::Decode, Clone, SpreadLayout, PackedLayout)]
+    #[cfg_attr(
+    feature = "std",
+    derive(scale_info::TypeInfo, ink_storage::traits::StorageLayout)
+    )]
+    #[derive(Debug)]
+    pub struct RoleGrant {
+        pub role: String,
+        pub expire_block: Option<u32>,
+    }
+
+    /// The RoleManage error types.
+    #[derive(Debug, PartialEq, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub enum Error {
+        /// Returned if the caller is not the core contract.
+        NotCore,
+        /// Returned if the role has not been added.
+        RoleNotFound,
+        /// Returned if the role has already been added.
+        DuplicateRole,
+        /// Returned if the privilege has not been added to authority_management.
+        PrivilegeNotFound,
+        /// Returned if a parent link would make a role inherit from itself.
+        RoleCycle,
+        /// Returned if the expire block of a grant has already passed.
+        InvalidExpiry,
+        /// Returned if the user does not have the role.
+        UserRoleNotFound,
+    }
+
+    /// The RoleManage result type.
+    pub type Result<T> = core::result::Result<T, Error>;
+
+    #[ink(event)]
+    pub struct RoleAdded {
+        #[ink(topic)]
+        name: String,
+    }
+
+    #[ink(event)]
+    pub struct RoleRemoved {
+        #[ink(topic)]
+        name: String,
+    }
+
+    #[ink(event)]
+    pub struct RoleParentAdded {
+        #[ink(topic)]
+        name: String,
+        #[ink(topic)]
+        parent: String,
+    }
+
+    #[ink(event)]
+    pub struct RoleParentRemoved {
+        #[ink(topic)]
+        name: String,
+        #[ink(topic)]
+        parent: String,
+    }
 
+    #[ink(event)]
+    pub struct RolePrivilegeGranted {
+        #[ink(topic)]
+        name: String,
+        #[ink(topic)]
+        privilege: String,
+    }
 
+    #[ink(event)]
+    pub struct RolePrivilegeRevoked {
+        #[ink(topic)]
+        name: String,
+        #[ink(topic)]
+        privilege: String,
+    }
+
+    #[ink(event)]
+    pub struct UserRoleGranted {
+        #[ink(topic)]
+        user: AccountId,
+        #[ink(topic)]
+        role: String,
+        expire_block: Option<u32>,
+    }
+
+    #[ink(event)]
+    pub struct UserRoleRevoked {
+        #[ink(topic)]
+        user: AccountId,
+        #[ink(topic)]
+        role: String,
+    }
 
     /// Manage the role of the rainbow protocol
     /// owner:the manager of contract
     /// index:the number of role
     /// role_map:HashMap of role index and name
     /// role_privileges:HashMap of role name and privileges
-    /// user_role:HashMap of user  and roles
+    /// user_role:HashMap of user  and role grants
+    /// role_parents:HashMap of role name and the roles it inherits privileges from
+    /// authority_management_addr:the address of authority_management, privileges are checked against it when set
     #[ink(storage)]
     pub struct RoleManage {
         owner:AccountId,
         index:u64,
         role_map:StorageHashMap<u64,String>,
         role_privileges:StorageHashMap<String,Vec<String>>,
-        user_role:StorageHashMap<AccountId,Vec<String>>,
+        user_role:StorageHashMap<AccountId,Vec<RoleGrant>>,
+        role_parents:StorageHashMap<String,Vec<String>>,
+        authority_management_addr:AccountId,
     }
 
     impl RoleManage {
@@ -39,30 +150,35 @@ mod role_manage {
                 index: 0,
                 role_map : StorageHashMap::new(),
                 role_privileges: StorageHashMap::new(),
-                user_role: StorageHashMap::new()
+                user_role: StorageHashMap::new(),
+                role_parents: StorageHashMap::new(),
+                authority_management_addr: AccountId::default(),
             };
             instance
         }
 
-        fn only_core(&self,sender:AccountId) {
-            assert_eq!(self.owner, sender);
+        fn only_core(&self,sender:AccountId) -> Result<()> {
+            if self.owner != sender {
+                return Err(Error::NotCore);
+            }
+            Ok(())
         }
 
         /// Add a role
         /// name : the name of role
-        /// # Panics
-        /// Only core contracts can be called
+        /// # Errors
+        /// NotCore if not called by the core contract, DuplicateRole if the role exists
         #[ink(message)]
-        pub fn add_role(&mut self, name: String) -> bool {
-            self.only_core(Self::env().caller());
+        pub fn add_role(&mut self, name: String) -> Result<()> {
+            self.only_core(Self::env().caller())?;
             assert_eq!(self.index + 1 > self.index, true);
-            let list = self.list_roles();
-            for i in  list{
-                assert_eq!(i != name, true);
+            if self.exists_role(name.clone()) {
+                return Err(Error::DuplicateRole);
             }
-            self.role_map.insert(self.index, name);
+            self.role_map.insert(self.index, name.clone());
             self.index += 1;
-            true
+            self.env().emit_event(RoleAdded { name });
+            Ok(())
         }
         /// Show all roles
         #[ink(message)]
@@ -76,41 +192,293 @@ mod role_manage {
             }
             role_vec
         }
+        /// Remove a role, its privileges and all users' grants of it
+        /// name : the name of role
+        /// # Errors
+        /// NotCore if not called by the core contract, RoleNotFound if the role does not exist
+        #[ink(message)]
+        pub fn remove_role(&mut self, name: String) -> Result<()> {
+            self.only_core(Self::env().caller())?;
+            let mut role_index = None;
+            for (index, role) in self.role_map.iter() {
+                if *role == name {
+                    role_index = Some(*index);
+                }
+            }
+            let role_index = role_index.ok_or(Error::RoleNotFound)?;
+            self.role_map.take(&role_index);
+            self.role_privileges.take(&name);
+            let mut revoked = Vec::new();
+            for (user, grants) in self.user_role.iter_mut() {
+                if grants.iter().any(|grant| grant.role == name) {
+                    grants.retain(|grant| grant.role != name);
+                    revoked.push(*user);
+                }
+            }
+            let mut unlinked = Vec::new();
+            for parent in self.role_parents.take(&name).unwrap_or(Vec::new()) {
+                unlinked.push((name.clone(), parent));
+            }
+            for (child, parents) in self.role_parents.iter_mut() {
+                if parents.contains(&name) {
+                    parents.retain(|parent| *parent != name);
+                    unlinked.push((child.clone(), name.clone()));
+                }
+            }
+            for user in revoked {
+                self.env().emit_event(UserRoleRevoked { user, role: name.clone() });
+            }
+            for (child, parent) in unlinked {
+                self.env().emit_event(RoleParentRemoved { name: child, parent });
+            }
+            self.env().emit_event(RoleRemoved { name });
+            Ok(())
+        }
+        /// Check whether a role exists
+        /// name : the name of role
+        #[ink(message)]
+        pub fn exists_role(&self, name: String) -> bool {
+            self.role_map.values().any(|role| *role == name)
+        }
+        /// Let a role inherit all privileges of a parent role
+        /// name : the name of role
+        /// parent : the name of parent role
+        /// # Errors
+        /// NotCore if not called by the core contract, RoleNotFound if a role does not exist,
+        /// RoleCycle if the link would make a role inherit from itself
+        #[ink(message)]
+        pub fn add_role_parent(&mut self, name: String, parent: String) -> Result<()> {
+            self.only_core(Self::env().caller())?;
+            if !self.exists_role(name.clone()) || !self.exists_role(parent.clone()) {
+                return Err(Error::RoleNotFound);
+            }
+            if name == parent || self.list_role_ancestors(parent.clone()).contains(&name) {
+                return Err(Error::RoleCycle);
+            }
+            let parents = self.role_parents.entry(name.clone()).or_insert(Vec::new());
+            if !parents.contains(&parent) {
+                parents.push(parent.clone());
+                self.env().emit_event(RoleParentAdded { name, parent });
+            }
+            Ok(())
+        }
+        /// Stop a role inheriting from a parent role
+        /// name : the name of role
+        /// parent : the name of parent role
+        /// # Errors
+        /// NotCore if not called by the core contract
+        #[ink(message)]
+        pub fn remove_role_parent(&mut self, name: String, parent: String) -> Result<()> {
+            self.only_core(Self::env().caller())?;
+            if let Some(parents) = self.role_parents.get_mut(&name) {
+                if parents.contains(&parent) {
+                    parents.retain(|p| *p != parent);
+                    self.env().emit_event(RoleParentRemoved { name, parent });
+                }
+            }
+            Ok(())
+        }
+        /// Show the direct parents of a role
+        /// name : the name of role
+        #[ink(message)]
+        pub fn get_role_parents(&self, name: String) -> Vec<String> {
+            self.role_parents.get(&name).cloned().unwrap_or(Vec::new())
+        }
+        /// Show all roles a role inherits from, directly or through its parents
+        /// name : the name of role
+        #[ink(message)]
+        pub fn list_role_ancestors(&self, name: String) -> Vec<String> {
+            let mut ancestors: Vec<String> = Vec::new();
+            let mut pending = self.get_role_parents(name);
+            while let Some(role) = pending.pop() {
+                if ancestors.contains(&role) {
+                    continue;
+                }
+                pending.append(&mut self.get_role_parents(role.clone()));
+                ancestors.push(role);
+            }
+            ancestors
+        }
+        /// Show all privileges of role, including the inherited ones
+        /// name : the name of role
+        #[ink(message)]
+        pub fn list_role_all_privileges(&self, name: String) -> Vec<String> {
+            let mut privilege_vec = Vec::new();
+            let mut roles = self.list_role_ancestors(name.clone());
+            roles.insert(0, name);
+            for role in roles {
+                for privilege in self.list_role_privileges(role) {
+                    if !privilege_vec.contains(&privilege) {
+                        privilege_vec.push(privilege);
+                    }
+                }
+            }
+            privilege_vec
+        }
         /// Get role by index
         #[ink(message)]
         pub fn query_role_by_index(&self, index: u64) -> String {
             self.role_map.get(&index).unwrap_or(&String::default()).clone()
         }
 
-        /// Add permissions to a role
+        /// Set the authority_management contract that privileges are checked against
+        /// addr:the address of authority_management
+        /// # Errors
+        /// NotCore if not called by the core contract
+        #[ink(message)]
+        pub fn set_authority_management(&mut self, addr: AccountId) -> Result<()> {
+            self.only_core(Self::env().caller())?;
+            self.authority_management_addr = addr;
+            Ok(())
+        }
+        /// Get the address of authority_management
+        #[ink(message)]
+        pub fn get_authority_management(&self) -> AccountId {
+            self.authority_management_addr
+        }
+        /// Add permissions to a role, a privilege is only stored once per role
         /// name:role's name
         /// privilege:privilege's name
-        /// # Panics
-        /// Only core contracts can be called
+        /// # Errors
+        /// NotCore if not called by the core contract, RoleNotFound if the role has not been added,
+        /// PrivilegeNotFound if authority_management is set and does not know the privilege
         #[ink(message)]
-        pub fn role_insert_privilege(&mut self ,name:String,privilege:String) -> bool {
-            self.only_core(Self::env().caller());
+        pub fn role_insert_privilege(&mut self ,name:String,privilege:String) -> Result<()> {
+            self.only_core(Self::env().caller())?;
+            if !self.exists_role(name.clone()) {
+                return Err(Error::RoleNotFound);
+            }
+            if self.authority_management_addr != AccountId::default() {
+                let authority_instance: AuthorityManagement = ink_env::call::FromAccountId::from_account_id(self.authority_management_addr);
+                if !authority_instance.exists_privilege(privilege.clone()) {
+                    return Err(Error::PrivilegeNotFound);
+                }
+            }
             let role_privilege_list = self.role_privileges.entry(name.clone()).or_insert(Vec::new());
-            role_privilege_list.push(privilege);
-            true
+            if !role_privilege_list.contains(&privilege) {
+                role_privilege_list.push(privilege.clone());
+                self.env().emit_event(RolePrivilegeGranted { name, privilege });
+            }
+            Ok(())
+        }
+        /// Remove a privilege from a role
+        /// name:role's name
+        /// privilege:privilege's name
+        /// # Errors
+        /// NotCore if not called by the core contract, RoleNotFound if the role has not been added,
+        /// PrivilegeNotFound if the role does not have the privilege
+        #[ink(message)]
+        pub fn role_remove_privilege(&mut self ,name:String,privilege:String) -> Result<()> {
+            self.only_core(Self::env().caller())?;
+            if !self.exists_role(name.clone()) {
+                return Err(Error::RoleNotFound);
+            }
+            let role_privilege_list = self.role_privileges.get_mut(&name).ok_or(Error::PrivilegeNotFound)?;
+            if !role_privilege_list.contains(&privilege) {
+                return Err(Error::PrivilegeNotFound);
+            }
+            role_privilege_list.retain(|p| *p != privilege);
+            self.env().emit_event(RolePrivilegeRevoked { name, privilege });
+            Ok(())
+        }
+        /// Remove a privilege from every role, used when the privilege is deleted
+        /// privilege:privilege's name
+        /// Returns the number of roles that had the privilege
+        /// # Errors
+        /// NotCore if not called by the core contract
+        #[ink(message)]
+        pub fn remove_privilege_from_roles(&mut self ,privilege:String) -> Result<u32> {
+            self.only_core(Self::env().caller())?;
+            let mut revoked = Vec::new();
+            for (name, role_privilege_list) in self.role_privileges.iter_mut() {
+                if role_privilege_list.contains(&privilege) {
+                    role_privilege_list.retain(|p| *p != privilege);
+                    revoked.push(name.clone());
+                }
+            }
+            let count = revoked.len() as u32;
+            for name in revoked {
+                self.env().emit_event(RolePrivilegeRevoked { name, privilege: privilege.clone() });
+            }
+            Ok(count)
         }
         /// Show all privileges of role
         #[ink(message)]
         pub fn list_role_privileges(&self,name:String) -> Vec<String> {
-           let v =  self.role_privileges.get(&name).unwrap().clone();
+           let v =  self.role_privileges.get(&name).cloned().unwrap_or(Vec::new());
             v
         }
-        /// Add a role to user
+        /// Add a role to user, a temporary grant of the role is made permanent
+        /// user:the address of user
+        /// role:the name of role
+        /// # Errors
+        /// NotCore if not called by the core contract, RoleNotFound if the role has not been added
+        #[ink(message)]
+        pub fn add_user_role(&mut self,user:AccountId,role:String) -> Result<()> {
+            self.only_core(Self::env().caller())?;
+            if !self.exists_role(role.clone()) {
+                return Err(Error::RoleNotFound);
+            }
+            self.insert_user_role(user, role, None);
+            Ok(())
+        }
+        /// Add a role to user that expires at a block.
+        /// If the user already has a grant of the role that lives as long or longer, it is kept
+        /// user:the address of user
+        /// role:the name of role
+        /// expire_block:the grant is ignored from this block on
+        /// # Errors
+        /// NotCore if not called by the core contract, RoleNotFound if the role has not been added,
+        /// InvalidExpiry if the expire block has passed
+        #[ink(message)]
+        pub fn add_temporary_user_role(&mut self,user:AccountId,role:String,expire_block:u32) -> Result<()> {
+            self.only_core(Self::env().caller())?;
+            if !self.exists_role(role.clone()) {
+                return Err(Error::RoleNotFound);
+            }
+            if expire_block <= self.env().block_number() {
+                return Err(Error::InvalidExpiry);
+            }
+            self.insert_user_role(user, role, Some(expire_block));
+            Ok(())
+        }
+        /// Remove all expired grants of every user
+        /// Returns the number of grants removed
+        #[ink(message)]
+        pub fn clear_expired_roles(&mut self) -> u32 {
+            let block = self.env().block_number();
+            let mut revoked = Vec::new();
+            for (user, grants) in self.user_role.iter_mut() {
+                for grant in grants.iter().filter(|grant| Self::grant_expired(grant, block)) {
+                    revoked.push((*user, grant.role.clone()));
+                }
+                grants.retain(|grant| !Self::grant_expired(grant, block));
+            }
+            let removed = revoked.len() as u32;
+            for (user, role) in revoked {
+                self.env().emit_event(UserRoleRevoked { user, role });
+            }
+            removed
+        }
+        /// Remove a role from user
         /// user:the address of user
         /// role:the name of role
-        /// # Panics
-        /// Only core contracts can be called
+        /// # Errors
+        /// NotCore if not called by the core contract, RoleNotFound if the role has not been added,
+        /// UserRoleNotFound if the user does not have the role
         #[ink(message)]
-        pub fn add_user_role(&mut self,user:AccountId,role:String) -> bool {
-            self.only_core(Self::env().caller());
-            let user_role_list = self.user_role.entry(user.clone()).or_insert(Vec::new());
-            user_role_list.push(role);
-            true
+        pub fn remove_user_role(&mut self,user:AccountId,role:String) -> Result<()> {
+            self.only_core(Self::env().caller())?;
+            if !self.exists_role(role.clone()) {
+                return Err(Error::RoleNotFound);
+            }
+            let user_role_list = self.user_role.get_mut(&user).ok_or(Error::UserRoleNotFound)?;
+            if !user_role_list.iter().any(|grant| grant.role == role) {
+                return Err(Error::UserRoleNotFound);
+            }
+            user_role_list.retain(|grant| grant.role != role);
+            self.env().emit_event(UserRoleRevoked { user, role });
+            Ok(())
         }
         /// Check if someone has a role
         #[ink(message)]
@@ -123,13 +491,34 @@ mod role_manage {
             }
             false
         }
-        /// Get all roles by user
+        /// Get all roles by user, expired grants are left out
         /// user: the address of user
         #[ink(message)]
         pub fn get_user_roles(&self,user:AccountId) -> Vec<String> {
-           let list =  self.user_role.get(&user).unwrap().clone();
+            let block = self.env().block_number();
+            let mut list = Vec::new();
+            for grant in self.get_user_role_grants(user) {
+                if !Self::grant_expired(&grant, block) {
+                    list.push(grant.role);
+                }
+            }
             list
         }
+        /// Get all role grants by user, including expired ones
+        /// user: the address of user
+        #[ink(message)]
+        pub fn get_user_role_grants(&self,user:AccountId) -> Vec<RoleGrant> {
+            self.user_role.get(&user).cloned().unwrap_or(Vec::new())
+        }
+        /// Show all users and their role grants
+        #[ink(message)]
+        pub fn list_user_roles(&self) -> BTreeMap<AccountId,Vec<RoleGrant>> {
+            let mut user_roles = BTreeMap::new();
+            for (user, roles) in self.user_role.iter() {
+                user_roles.insert(*user, roles.clone());
+            }
+            user_roles
+        }
         /// Check if someone has a privilege
         #[ink(message)]
         pub fn check_user_privilege(&self,user:AccountId,privilege:String) -> bool {
@@ -141,19 +530,50 @@ mod role_manage {
             }
             false
         }
-        /// Get all privileges by user
+        /// Get all privileges by user, including the ones inherited from parent roles
         /// user: the address of user
         #[ink(message)]
         pub fn get_user_privilege(&self,user:AccountId) -> Vec<String> {
             let mut privilege_vec = Vec::new();
             // role vec
-            let list =  self.user_role.get(&user).unwrap().clone();
+            let list =  self.get_user_roles(user);
             for i in list {
-               let mut privileges =  self.role_privileges.get(&i).unwrap().clone();
-                privilege_vec.append(&mut privileges);
+                for privilege in self.list_role_all_privileges(i) {
+                    if !privilege_vec.contains(&privilege) {
+                        privilege_vec.push(privilege);
+                    }
+                }
             }
             privilege_vec
         }
+
+        fn insert_user_role(&mut self,user:AccountId,role:String,expire_block:Option<u32>) {
+            let user_role_list = self.user_role.entry(user).or_insert(Vec::new());
+            if let Some(grant) = user_role_list.iter().find(|grant| grant.role == role) {
+                if Self::lives_as_long(grant.expire_block, expire_block) {
+                    return;
+                }
+            }
+            user_role_list.retain(|grant| grant.role != role);
+            user_role_list.push(RoleGrant { role: role.clone(), expire_block });
+            self.env().emit_event(UserRoleGranted { user, role, expire_block });
+        }
+
+        /// Check a grant expiring at expire_block lives at least as long as one expiring at other
+        fn lives_as_long(expire_block:Option<u32>,other:Option<u32>) -> bool {
+            match (expire_block, other) {
+                (None, _) => true,
+                (Some(_), None) => false,
+                (Some(expire_block), Some(other)) => expire_block >= other,
+            }
+        }
+
+        fn grant_expired(grant:&RoleGrant,block:u32) -> bool {
+            match grant.expire_block {
+                Some(expire_block) => block >= expire_block,
+                None => false,
+            }
+        }
     }
 
     #[cfg(test)]
@@ -165,22 +585,204 @@ mod role_manage {
         /// Imports `ink_lang` so we can use `#[ink::test]`.
         use ink_lang as ink;
 
+        type Event = <RoleManage as ::ink_lang::BaseEvent>::Type;
+
+        fn decode_event(event: &ink_env::test::EmittedEvent) -> Event {
+            <Event as scale::Decode>::decode(&mut &event.data[..])
+                .expect("encountered invalid contract event data buffer")
+        }
+
         #[ink::test]
         fn add_role_works() {
             let mut role_manage = RoleManage::new();
-            role_manage.add_role(String::from("test"));
+            assert_eq!(role_manage.add_role(String::from("test")), Ok(()));
             assert!(role_manage.query_role_by_index(0)== String::from("test"));
+            let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+            assert_eq!(emitted_events.len(), 1);
+            assert!(matches!(decode_event(&emitted_events[0]), Event::RoleAdded(RoleAdded { name }) if name == String::from("test")));
 
         }
         #[ink::test]
+        fn add_role_fails_for_duplicate() {
+            let mut role_manage = RoleManage::new();
+            assert_eq!(role_manage.add_role(String::from("test")), Ok(()));
+            assert_eq!(role_manage.add_role(String::from("test")), Err(Error::DuplicateRole));
+            assert_eq!(role_manage.remove_role(String::from("none")), Err(Error::RoleNotFound));
+        }
+        #[ink::test]
+        fn add_role_fails_for_non_core() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                    .expect("Cannot get accounts");
+            let mut role_manage = RoleManage::new();
+            let callee = ink_env::account_id::<ink_env::DefaultEnvironment>()
+                .unwrap_or([0x0; 32].into());
+            ink_env::test::push_execution_context::<ink_env::DefaultEnvironment>(
+                accounts.bob,
+                callee,
+                1000000,
+                1000000,
+                ink_env::test::CallData::new(ink_env::call::Selector::new([0x00; 4])),
+            );
+            assert_eq!(role_manage.add_role(String::from("test")), Err(Error::NotCore));
+        }
+        #[ink::test]
+        fn unknown_user_queries_are_empty() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                    .expect("Cannot get accounts");
+            let role_manage = RoleManage::new();
+            assert!(role_manage.get_user_roles(accounts.bob).is_empty());
+            assert!(role_manage.get_user_privilege(accounts.bob).is_empty());
+            assert!(role_manage.list_role_privileges(String::from("none")).is_empty());
+            assert!(!role_manage.check_user_privilege(accounts.bob,String::from("vote")));
+        }
+        #[ink::test]
         fn add_user_role_works() {
             let accounts =
                 ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
                     .expect("Cannot get accounts");
             let mut role_manage = RoleManage::new();
-            role_manage.add_user_role(accounts.alice,String::from("test"));
+            assert_eq!(role_manage.add_user_role(accounts.alice,String::from("test")), Err(Error::RoleNotFound));
+            assert_eq!(role_manage.add_temporary_user_role(accounts.alice,String::from("test"),1), Err(Error::RoleNotFound));
+            assert_eq!(role_manage.add_role(String::from("test")), Ok(()));
+            assert_eq!(role_manage.add_user_role(accounts.alice,String::from("test")), Ok(()));
             assert!(role_manage.check_user_role(accounts.alice,String::from("test"))== true);
 
         }
+        #[ink::test]
+        fn remove_role_works() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                    .expect("Cannot get accounts");
+            let mut role_manage = RoleManage::new();
+            assert_eq!(role_manage.add_role(String::from("test")), Ok(()));
+            assert_eq!(role_manage.role_insert_privilege(String::from("test"),String::from("vote")), Ok(()));
+            assert_eq!(role_manage.role_remove_privilege(String::from("test"),String::from("vote")), Ok(()));
+            assert!(role_manage.list_role_privileges(String::from("test")).is_empty());
+            assert_eq!(role_manage.add_role(String::from("other")), Ok(()));
+            assert_eq!(role_manage.role_insert_privilege(String::from("test"),String::from("vote")), Ok(()));
+            assert_eq!(role_manage.role_insert_privilege(String::from("other"),String::from("vote")), Ok(()));
+            assert_eq!(role_manage.remove_privilege_from_roles(String::from("vote")), Ok(2));
+            assert!(role_manage.list_role_all_privileges(String::from("other")).is_empty());
+            assert_eq!(role_manage.add_role_parent(String::from("test"),String::from("other")), Ok(()));
+            assert_eq!(role_manage.remove_role(String::from("other")), Ok(()));
+            assert!(role_manage.get_role_parents(String::from("test")).is_empty());
+            let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+            let count = emitted_events.len();
+            assert!(matches!(
+                decode_event(&emitted_events[count - 2]),
+                Event::RoleParentRemoved(RoleParentRemoved { name, parent }) if name == String::from("test") && parent == String::from("other")
+            ));
+            assert_eq!(role_manage.add_user_role(accounts.alice,String::from("test")), Ok(()));
+            assert_eq!(role_manage.remove_role(String::from("test")), Ok(()));
+            assert!(role_manage.list_roles().is_empty());
+            assert!(role_manage.check_user_role(accounts.alice,String::from("test"))== false);
+            let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+            let count = emitted_events.len();
+            assert!(matches!(
+                decode_event(&emitted_events[count - 2]),
+                Event::UserRoleRevoked(UserRoleRevoked { user, role }) if user == accounts.alice && role == String::from("test")
+            ));
+            assert!(matches!(decode_event(&emitted_events[count - 1]), Event::RoleRemoved(_)));
+        }
+        #[ink::test]
+        fn role_parent_works() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                    .expect("Cannot get accounts");
+            let mut role_manage = RoleManage::new();
+            assert_eq!(role_manage.add_role(String::from("member")), Ok(()));
+            assert_eq!(role_manage.add_role(String::from("moderator")), Ok(()));
+            assert_eq!(role_manage.add_role(String::from("admin")), Ok(()));
+            assert_eq!(role_manage.role_insert_privilege(String::from("member"),String::from("vote")), Ok(()));
+            assert_eq!(role_manage.role_insert_privilege(String::from("moderator"),String::from("ban")), Ok(()));
+            assert_eq!(role_manage.add_role_parent(String::from("moderator"),String::from("member")), Ok(()));
+            assert_eq!(role_manage.add_role_parent(String::from("admin"),String::from("moderator")), Ok(()));
+            assert_eq!(role_manage.add_user_role(accounts.alice,String::from("admin")), Ok(()));
+            assert!(role_manage.check_user_privilege(accounts.alice,String::from("vote")));
+            assert!(role_manage.check_user_privilege(accounts.alice,String::from("ban")));
+            assert_eq!(role_manage.list_role_ancestors(String::from("admin")).len(), 2);
+        }
+        #[ink::test]
+        fn role_parent_cycle_fails() {
+            let mut role_manage = RoleManage::new();
+            assert_eq!(role_manage.add_role(String::from("member")), Ok(()));
+            assert_eq!(role_manage.add_role(String::from("admin")), Ok(()));
+            assert_eq!(role_manage.add_role_parent(String::from("admin"),String::from("member")), Ok(()));
+            assert_eq!(role_manage.add_role_parent(String::from("member"),String::from("admin")), Err(Error::RoleCycle));
+            assert_eq!(role_manage.add_role_parent(String::from("admin"),String::from("admin")), Err(Error::RoleCycle));
+        }
+        #[ink::test]
+        fn role_insert_privilege_works() {
+            let mut role_manage = RoleManage::new();
+            assert_eq!(role_manage.role_insert_privilege(String::from("test"),String::from("vote")), Err(Error::RoleNotFound));
+            assert_eq!(role_manage.add_role(String::from("test")), Ok(()));
+            assert_eq!(role_manage.role_insert_privilege(String::from("test"),String::from("vote")), Ok(()));
+            assert_eq!(role_manage.role_insert_privilege(String::from("test"),String::from("vote")), Ok(()));
+            assert_eq!(role_manage.list_role_privileges(String::from("test")).len(), 1);
+            let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+            assert_eq!(emitted_events.len(), 2);
+            assert!(matches!(decode_event(&emitted_events[1]), Event::RolePrivilegeGranted(RolePrivilegeGranted { privilege, .. }) if privilege == String::from("vote")));
+        }
+        #[ink::test]
+        fn temporary_user_role_works() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                    .expect("Cannot get accounts");
+            let mut role_manage = RoleManage::new();
+            assert_eq!(role_manage.add_role(String::from("member")), Ok(()));
+            assert_eq!(role_manage.add_role(String::from("treasurer")), Ok(()));
+            assert_eq!(role_manage.add_user_role(accounts.alice,String::from("member")), Ok(()));
+            assert_eq!(role_manage.add_temporary_user_role(accounts.alice,String::from("treasurer"),1), Ok(()));
+            assert!(role_manage.check_user_role(accounts.alice,String::from("treasurer")));
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>().expect("Cannot advance block");
+            assert!(!role_manage.check_user_role(accounts.alice,String::from("treasurer")));
+            assert!(role_manage.check_user_role(accounts.alice,String::from("member")));
+            assert_eq!(role_manage.clear_expired_roles(), 1);
+            assert_eq!(role_manage.get_user_role_grants(accounts.alice).len(), 1);
+            let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+            assert!(matches!(
+                decode_event(emitted_events.last().unwrap()),
+                Event::UserRoleRevoked(UserRoleRevoked { user, role }) if user == accounts.alice && role == String::from("treasurer")
+            ));
+        }
+        #[ink::test]
+        fn temporary_user_role_keeps_longer_grant() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                    .expect("Cannot get accounts");
+            let mut role_manage = RoleManage::new();
+            assert_eq!(role_manage.add_role(String::from("member")), Ok(()));
+            assert_eq!(role_manage.add_user_role(accounts.alice,String::from("member")), Ok(()));
+            assert_eq!(role_manage.add_temporary_user_role(accounts.alice,String::from("member"),1), Ok(()));
+            assert!(role_manage.get_user_role_grants(accounts.alice)[0].expire_block.is_none());
+            assert_eq!(role_manage.add_temporary_user_role(accounts.bob,String::from("member"),5), Ok(()));
+            assert_eq!(role_manage.add_temporary_user_role(accounts.bob,String::from("member"),3), Ok(()));
+            assert_eq!(role_manage.get_user_role_grants(accounts.bob)[0].expire_block, Some(5));
+            assert_eq!(role_manage.add_user_role(accounts.bob,String::from("member")), Ok(()));
+            assert_eq!(role_manage.get_user_role_grants(accounts.bob).len(), 1);
+            assert!(role_manage.get_user_role_grants(accounts.bob)[0].expire_block.is_none());
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>().expect("Cannot advance block");
+            assert!(role_manage.check_user_role(accounts.alice,String::from("member")));
+        }
+        #[ink::test]
+        fn remove_user_role_works() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                    .expect("Cannot get accounts");
+            let mut role_manage = RoleManage::new();
+            assert_eq!(role_manage.remove_user_role(accounts.alice,String::from("test")), Err(Error::RoleNotFound));
+            assert_eq!(role_manage.add_role(String::from("test")), Ok(()));
+            assert_eq!(role_manage.remove_user_role(accounts.alice,String::from("test")), Err(Error::UserRoleNotFound));
+            assert_eq!(role_manage.role_remove_privilege(String::from("test"),String::from("vote")), Err(Error::PrivilegeNotFound));
+            assert_eq!(role_manage.add_user_role(accounts.alice,String::from("test")), Ok(()));
+            assert_eq!(role_manage.remove_user_role(accounts.alice,String::from("test")), Ok(()));
+            assert!(role_manage.check_user_role(accounts.alice,String::from("test"))== false);
+            let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+            assert_eq!(emitted_events.len(), 3);
+            assert!(matches!(decode_event(&emitted_events[1]), Event::UserRoleGranted(UserRoleGranted { user, expire_block: None, .. }) if user == accounts.alice));
+            assert!(matches!(decode_event(&emitted_events[2]), Event::UserRoleRevoked(UserRoleRevoked { user, .. }) if user == accounts.alice));
+        }
     }
 }
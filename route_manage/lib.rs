@@ -3,24 +3,121 @@ extern crate alloc;
 use ink_lang as ink;
 pub use self::route_manage::{
     RouteManage,
+    RouteVersion,
+    RouteInfo,
 };
 #[allow(unused_imports)]
 #[ink::contract]
 mod route_manage {
 
     use alloc::string::String;
+    use ink_prelude::vec::Vec;
     use ink_prelude::collections::BTreeMap;
-    use ink_storage::{collections::HashMap as StorageHashMap, };
+    use ink_storage::{
+        collections::{
+            HashMap as StorageHashMap,
+            Vec as StorageVec,
+        },
+        traits::{
+            PackedLayout,
+            SpreadLayout,
+        }
+    };
+
+    /// An address a route has pointed to
+    /// version:the version of route, starting at 1
+    /// value:the address of route
+    /// block:the block number when the route was pointed to the address
+    #[derive(scale::Encode, scale::Decode, Clone, SpreadLayout, PackedLayout)]
+    #[cfg_attr(
+    feature = "std",
+    derive(scale_info::TypeInfo, ink_storage::traits::StorageLayout)
+    )]
+    #[derive(Debug)]
+    pub struct RouteVersion {
+        pub version: u32,
+        pub value: AccountId,
+        pub block: u32,
+    }
+
+    /// The most routes returned by one page
+    const MAX_PAGE_SIZE: u32 = 100;
+
+    /// Metadata of a route
+    /// code_hash:the code hash the contract at the route was deployed from
+    /// interface_id:the interface the contract at the route implements, e.g. income_category
+    /// description:what the route is used for
+    #[derive(scale::Encode, scale::Decode, Clone, SpreadLayout, PackedLayout)]
+    #[cfg_attr(
+    feature = "std",
+    derive(scale_info::TypeInfo, ink_storage::traits::StorageLayout)
+    )]
+    #[derive(Debug)]
+    pub struct RouteInfo {
+        pub code_hash: Hash,
+        pub interface_id: String,
+        pub description: String,
+    }
+
+    #[ink(event)]
+    pub struct RouteAdded {
+        #[ink(topic)]
+        name: String,
+        #[ink(topic)]
+        value: AccountId,
+    }
+
+    #[ink(event)]
+    pub struct RouteChanged {
+        #[ink(topic)]
+        name: String,
+        old_value: AccountId,
+        #[ink(topic)]
+        new_value: AccountId,
+    }
+
+    #[ink(event)]
+    pub struct RouteRolledBack {
+        #[ink(topic)]
+        name: String,
+        version: u32,
+        #[ink(topic)]
+        value: AccountId,
+    }
+
+    #[ink(event)]
+    pub struct RouteInfoChanged {
+        #[ink(topic)]
+        name: String,
+        code_hash: Hash,
+        #[ink(topic)]
+        interface_id: String,
+    }
+
+    #[ink(event)]
+    pub struct RouteRemoved {
+        #[ink(topic)]
+        name: String,
+        value: AccountId,
+    }
 
     /// Manage the route of the rainbow protocol
     /// owner : the manager of contract
     /// index : the index of all route
     /// route_map:HashMap of route name and address
+    /// route_history:HashMap of route name and every address it has pointed to
+    /// route_info:HashMap of route name and metadata
+    /// route_names:all route names, used to page through the routes
+    /// route_positions:HashMap of route name and its position in route_names
     #[ink(storage)]
     pub struct RouteManage {
         owner:AccountId,
         index:u64,
         route_map:StorageHashMap<String,AccountId>,
+        route_history:StorageHashMap<String,Vec<RouteVersion>>,
+        route_info:StorageHashMap<String,RouteInfo>,
+        route_names:StorageVec<String>,
+        route_positions:StorageHashMap<String,u32>,
     }
 
     impl RouteManage {
@@ -30,13 +127,17 @@ mod route_manage {
                 owner:Self::env().caller(),
                 index: 0,
                 route_map : StorageHashMap::new(),
+                route_history : StorageHashMap::new(),
+                route_info : StorageHashMap::new(),
+                route_names : StorageVec::new(),
+                route_positions : StorageHashMap::new(),
             }
         }
         fn only_core(&self,sender:AccountId) {
             assert_eq!(self.owner, sender);
         }
 
-        /// Add a route
+        /// Add a route, an existing route is repointed and its metadata is cleared
         /// name : the name of route
         /// value : the address of route
         /// # Panics
@@ -45,8 +146,15 @@ mod route_manage {
         pub fn add_route(&mut self, name: String,value:AccountId) -> bool {
             self.only_core(Self::env().caller());
             assert_eq!(self.index + 1 > self.index, true);
-            self.route_map.insert(name,value);
+            if self.route_map.insert(name.clone(),value).is_none() {
+                self.route_positions.insert(name.clone(), self.route_names.len());
+                self.route_names.push(name.clone());
+            } else {
+                self.route_info.take(&name);
+            }
             self.index += 1;
+            self.record_route_version(name.clone(), value);
+            self.env().emit_event(RouteAdded { name, value });
             true
         }
         /// Show all route
@@ -66,23 +174,211 @@ mod route_manage {
             }
             hash_map
         }
+        /// Get the number of routes
+        #[ink(message)]
+        pub fn route_count(&self) -> u32 {
+            self.route_names.len()
+        }
+        /// Show a page of routes
+        /// offset : the position of the first route
+        /// limit : the most routes to return, at most 100
+        #[ink(message)]
+        pub fn list_route_page(&self, offset: u32, limit: u32) -> Vec<(String,AccountId)> {
+            let end = core::cmp::min(
+                offset.saturating_add(core::cmp::min(limit, MAX_PAGE_SIZE)),
+                self.route_names.len()
+            );
+            let mut routes = Vec::new();
+            for position in offset..end {
+                let name = self.route_names.get(position).unwrap().clone();
+                let value = self.query_route_by_name(name.clone());
+                routes.push((name, value));
+            }
+            routes
+        }
+        /// Show routes whose name starts with a prefix, e.g. "dao."
+        /// Returns the routes found and the cursor to pass as start for the next page,
+        /// the cursor equals route_count when there are no more routes
+        /// prefix : the prefix of route name
+        /// start : the position to start searching from, 0 for the first page
+        /// limit : the most routes to return, at most 100
+        #[ink(message)]
+        pub fn list_route_by_prefix(&self, prefix: String, start: u32, limit: u32) -> (Vec<(String,AccountId)>, u32) {
+            let limit = core::cmp::min(limit, MAX_PAGE_SIZE) as usize;
+            let mut routes = Vec::new();
+            let mut position = start;
+            while position < self.route_names.len() && routes.len() < limit {
+                let name = self.route_names.get(position).unwrap();
+                if name.starts_with(prefix.as_str()) {
+                    routes.push((name.clone(), self.query_route_by_name(name.clone())));
+                }
+                position += 1;
+            }
+            (routes, position)
+        }
         /// Get a route by name
         /// name:the name of route
         #[ink(message)]
         pub fn query_route_by_name(&self, name: String) -> AccountId {
             self.route_map.get(&name).copied().unwrap_or(AccountId::default())
         }
-        /// Change routing address
+        /// Change routing address, the previous address is kept in the history.
+        /// The metadata of the route is cleared and has to be set again for the new address
         /// name : the name of route
         /// value : the address of route
+        /// Returns false if the route does not exist
         /// # Panics
         /// Only core contracts can be called
         #[ink(message)]
         pub fn change_route(&mut self,name:String,value:AccountId) -> bool {
             self.only_core(Self::env().caller());
-            self.route_map[&name] = value;
+            let old_value = match self.route_map.get(&name) {
+                Some(old_value) => *old_value,
+                None => return false,
+            };
+            self.route_map.insert(name.clone(), value);
+            self.route_info.take(&name);
+            self.record_route_version(name.clone(), value);
+            self.env().emit_event(RouteChanged { name, old_value, new_value: value });
+            true
+        }
+        /// Point a route back to the address of an earlier version.
+        /// The rollback is recorded as a new version and the metadata of the route is cleared
+        /// name : the name of route
+        /// version : the version to roll back to
+        /// Returns false if the route or the version does not exist
+        /// # Panics
+        /// Only core contracts can be called
+        #[ink(message)]
+        pub fn rollback_route(&mut self,name:String,version:u32) -> bool {
+            self.only_core(Self::env().caller());
+            if !self.route_map.contains_key(&name) {
+                return false;
+            }
+            let value = self.query_route_at_version(name.clone(), version);
+            if value == AccountId::default() {
+                return false;
+            }
+            self.route_map.insert(name.clone(), value);
+            self.route_info.take(&name);
+            self.record_route_version(name.clone(), value);
+            self.env().emit_event(RouteRolledBack { name, version, value });
+            true
+        }
+        /// Get the address of a route at a version
+        /// name : the name of route
+        /// version : the version of route
+        #[ink(message)]
+        pub fn query_route_at_version(&self,name:String,version:u32) -> AccountId {
+            self.route_history(name)
+                .iter()
+                .find(|route_version| route_version.version == version)
+                .map(|route_version| route_version.value)
+                .unwrap_or(AccountId::default())
+        }
+        /// Get the current version of a route, 0 if it was never added
+        /// name : the name of route
+        #[ink(message)]
+        pub fn query_route_version(&self,name:String) -> u32 {
+            self.route_history.get(&name).map(|history| history.len() as u32).unwrap_or(0)
+        }
+        /// Show every address a route has pointed to, oldest first
+        /// name : the name of route
+        #[ink(message)]
+        pub fn route_history(&self,name:String) -> Vec<RouteVersion> {
+            self.route_history.get(&name).cloned().unwrap_or(Vec::new())
+        }
+        /// Delete a route
+        /// name : the name of route
+        /// # Panics
+        /// Only core contracts can be called
+        #[ink(message)]
+        pub fn remove_route(&mut self,name:String) -> bool {
+            self.only_core(Self::env().caller());
+            let value = self.route_map.take(&name);
+            assert!(value.is_some());
+            self.route_info.take(&name);
+            let position = self.route_positions.take(&name).unwrap();
+            self.route_names.swap_remove(position);
+            if let Some(moved) = self.route_names.get(position) {
+                self.route_positions.insert(moved.clone(), position);
+            }
+            self.env().emit_event(RouteRemoved { name, value: value.unwrap() });
+            true
+        }
+
+        /// Set the metadata of a route.
+        /// It has to be set again whenever the route is pointed to another address
+        /// name : the name of route
+        /// code_hash : the code hash the contract was deployed from
+        /// interface_id : the interface the contract implements
+        /// description : what the route is used for
+        /// Returns false if the route does not exist
+        /// # Panics
+        /// Only core contracts can be called
+        #[ink(message)]
+        pub fn set_route_info(&mut self,name:String,code_hash:Hash,interface_id:String,description:String) -> bool {
+            self.only_core(Self::env().caller());
+            if !self.route_map.contains_key(&name) {
+                return false;
+            }
+            self.route_info.insert(name.clone(), RouteInfo {
+                code_hash,
+                interface_id: interface_id.clone(),
+                description,
+            });
+            self.env().emit_event(RouteInfoChanged { name, code_hash, interface_id });
             true
         }
+        /// Get the metadata of a route
+        /// name : the name of route
+        #[ink(message)]
+        pub fn get_route_info(&self,name:String) -> Option<RouteInfo> {
+            self.route_info.get(&name).cloned()
+        }
+        /// Get a route by name, but only if it implements the expected interface.
+        /// The default address is returned for routes without metadata
+        /// name : the name of route
+        /// interface_id : the interface the caller expects
+        #[ink(message)]
+        pub fn query_typed_route(&self,name:String,interface_id:String) -> AccountId {
+            match self.route_info.get(&name) {
+                Some(info) if info.interface_id == interface_id => self.query_route_by_name(name),
+                _ => AccountId::default(),
+            }
+        }
+        /// Get a route by name that must implement the expected interface,
+        /// the default address is returned if the route does not exist
+        /// name : the name of route
+        /// interface_id : the interface the caller expects
+        /// # Panics
+        /// The route exists but has no metadata or implements another interface
+        #[ink(message)]
+        pub fn query_checked_route(&self,name:String,interface_id:String) -> AccountId {
+            let value = self.query_route_by_name(name.clone());
+            if value != AccountId::default() {
+                let info = self.route_info.get(&name);
+                assert!(info.map_or(false, |info| info.interface_id == interface_id), "route does not implement the interface");
+            }
+            value
+        }
+        /// Check that a route was registered with the expected code hash
+        /// name : the name of route
+        /// code_hash : the expected code hash
+        #[ink(message)]
+        pub fn verify_route_code_hash(&self,name:String,code_hash:Hash) -> bool {
+            match self.route_info.get(&name) {
+                Some(info) => info.code_hash == code_hash,
+                None => false,
+            }
+        }
+
+        fn record_route_version(&mut self,name:String,value:AccountId) {
+            let block = self.env().block_number();
+            let history = self.route_history.entry(name).or_insert(Vec::new());
+            let version = history.len() as u32 + 1;
+            history.push(RouteVersion { version, value, block });
+        }
     }
 
 
@@ -96,6 +392,8 @@ mod route_manage {
         /// Imports `ink_lang` so we can use `#[ink::test]`.
         use ink_lang as ink;
 
+        type Event = <RouteManage as ::ink_lang::BaseEvent>::Type;
+
 
         #[ink::test]
         fn add_route_works() {
@@ -116,6 +414,99 @@ mod route_manage {
             route_manage.add_route(String::from("test"),accounts.alice);
             route_manage.change_route(String::from("test"),accounts.bob);
             assert!(route_manage.query_route_by_name(String::from("test")) == accounts.bob);
+            let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+            assert_eq!(emitted_events.len(), 2);
+            let decoded_event = <Event as scale::Decode>::decode(&mut &emitted_events[1].data[..])
+                .expect("encountered invalid contract event data buffer");
+            assert!(matches!(
+                decoded_event,
+                Event::RouteChanged(RouteChanged { old_value, new_value, .. }) if old_value == accounts.alice && new_value == accounts.bob
+            ));
+        }
+        #[ink::test]
+        fn rollback_route_works() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                    .expect("Cannot get accounts");
+            let mut route_manage = RouteManage::new();
+            route_manage.add_route(String::from("test"),accounts.alice);
+            route_manage.change_route(String::from("test"),accounts.bob);
+            assert_eq!(route_manage.query_route_version(String::from("test")), 2);
+            assert!(route_manage.query_route_at_version(String::from("test"),1) == accounts.alice);
+            assert!(route_manage.rollback_route(String::from("test"),1));
+            assert!(route_manage.query_route_by_name(String::from("test")) == accounts.alice);
+            let history = route_manage.route_history(String::from("test"));
+            assert_eq!(history.len(), 3);
+            assert!(history[2].value == accounts.alice);
+            assert!(!route_manage.rollback_route(String::from("test"),9));
+            assert!(!route_manage.change_route(String::from("none"),accounts.bob));
+        }
+        #[ink::test]
+        fn route_info_works() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                    .expect("Cannot get accounts");
+            let mut route_manage = RouteManage::new();
+            route_manage.add_route(String::from("test"),accounts.alice);
+            assert!(route_manage.query_typed_route(String::from("test"),String::from("erc20")) == AccountId::default());
+            assert!(route_manage.set_route_info(String::from("test"),Hash::from([0x01; 32]),String::from("income_category"),String::from("fees")));
+            assert!(route_manage.query_typed_route(String::from("test"),String::from("income_category")) == accounts.alice);
+            assert!(route_manage.query_typed_route(String::from("test"),String::from("erc20")) == AccountId::default());
+            assert!(route_manage.verify_route_code_hash(String::from("test"),Hash::from([0x01; 32])));
+            assert!(!route_manage.verify_route_code_hash(String::from("test"),Hash::from([0x02; 32])));
+            assert!(!route_manage.set_route_info(String::from("none"),Hash::from([0x01; 32]),String::from("erc20"),String::new()));
+            route_manage.change_route(String::from("test"),accounts.bob);
+            assert!(route_manage.get_route_info(String::from("test")).is_none());
+            assert!(route_manage.query_typed_route(String::from("test"),String::from("income_category")) == AccountId::default());
+            assert!(!route_manage.verify_route_code_hash(String::from("test"),Hash::from([0x01; 32])));
+            assert!(route_manage.query_checked_route(String::from("none"),String::from("erc20")) == AccountId::default());
+            assert!(route_manage.set_route_info(String::from("test"),Hash::from([0x01; 32]),String::from("income_category"),String::from("fees")));
+            route_manage.add_route(String::from("test"),accounts.alice);
+            assert!(route_manage.get_route_info(String::from("test")).is_none());
+        }
+        #[ink::test]
+        #[should_panic(expected = "route does not implement the interface")]
+        fn query_checked_route_fails_without_info() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                    .expect("Cannot get accounts");
+            let mut route_manage = RouteManage::new();
+            route_manage.add_route(String::from("income_category"),accounts.alice);
+            route_manage.query_checked_route(String::from("income_category"),String::from("income_category"));
+        }
+        #[ink::test]
+        fn list_route_page_works() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                    .expect("Cannot get accounts");
+            let mut route_manage = RouteManage::new();
+            route_manage.add_route(String::from("dao.vote"),accounts.alice);
+            route_manage.add_route(String::from("erc20"),accounts.bob);
+            route_manage.add_route(String::from("dao.treasury"),accounts.eve);
+            assert_eq!(route_manage.route_count(), 3);
+            let page = route_manage.list_route_page(1, 5);
+            assert_eq!(page.len(), 2);
+            assert!(page[0] == (String::from("erc20"), accounts.bob));
+            let (routes, cursor) = route_manage.list_route_by_prefix(String::from("dao."), 0, 1);
+            assert_eq!(routes.len(), 1);
+            assert_eq!(cursor, 1);
+            let (routes, cursor) = route_manage.list_route_by_prefix(String::from("dao."), cursor, 1);
+            assert!(routes[0] == (String::from("dao.treasury"), accounts.eve));
+            assert_eq!(cursor, 3);
+            route_manage.remove_route(String::from("dao.vote"));
+            assert_eq!(route_manage.route_count(), 2);
+            assert!(route_manage.list_route_page(0, 1)[0].0 == String::from("dao.treasury"));
+        }
+        #[ink::test]
+        fn remove_route_works() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                    .expect("Cannot get accounts");
+            let mut route_manage = RouteManage::new();
+            route_manage.add_route(String::from("test"),accounts.alice);
+            route_manage.remove_route(String::from("test"));
+            assert!(route_manage.query_route_by_name(String::from("test")) == AccountId::default());
+            assert_eq!(ink_env::test::recorded_events().count(), 2);
         }
     }
 }
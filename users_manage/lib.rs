@@ -1,11 +1,16 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 extern crate alloc;
+pub use self::users_manage::{
+    UsersManage
+};
 use ink_lang as ink;
 
 #[ink::contract]
 mod users_manage {
     use alloc::string::String;
     use ink_prelude::vec::Vec;
+    use erc20::Erc20;
+    use kernel::Kernel;
     use ink_storage::{
         collections::HashMap as StorageHashMap,
         traits::{
@@ -13,14 +18,20 @@ mod users_manage {
             SpreadLayout,
         }
     };
+    const MAX_REFERRAL_RATE: u32 = 10000;
+    const MAX_CODE_LENGTH: usize = 32;
+    const HASHED_CODE_BYTES: usize = 8;
+    /// The privilege checked through the kernel to moderate users
+    const MODERATE_PRIVILEGE: &str = "users_manage.moderate";
     /// User details
     /// id:the id of user
     /// nickname:the nickname of user
     /// profile:the profile of user
-    /// code:the code of user
     /// address:the address of user
     /// referer:the invitee of user
     /// childs : all subordinates of user
+    /// active : false once the user has deactivated the account
+    /// banned : true while the user is banned by a moderator
     #[derive(scale::Encode, scale::Decode, Clone, SpreadLayout, PackedLayout)]
     #[cfg_attr(
     feature = "std",
@@ -32,22 +43,111 @@ mod users_manage {
         id:u128,
         nickname:String,
         profile:String,
-        code:u128,
         address:AccountId,
         referer:AccountId,
-        childs:Vec<AccountId>
+        childs:Vec<AccountId>,
+        active:bool,
+        banned:bool
+    }
+
+    /// Invitation code details
+    /// owner:the user who invites with this code
+    /// max_uses:the most users that can join with this code, 0 for no limit
+    /// uses:the number of users that joined with this code
+    /// expire_block:the code can not be used from this block on
+    /// revoked:the owner no longer accepts this code
+    #[derive(scale::Encode, scale::Decode, Clone, SpreadLayout, PackedLayout)]
+    #[cfg_attr(
+    feature = "std",
+    derive(scale_info::TypeInfo, ink_storage::traits::StorageLayout)
+    )]
+    #[derive(Debug)]
+    pub struct InvitationCode {
+        owner:AccountId,
+        max_uses:u32,
+        uses:u32,
+        expire_block:Option<u32>,
+        revoked:bool
     }
 
     /// It manages users of the entire rainbow protocol
     /// user_info :HashMap of user address and user detail
-    /// code_user :HashMap of user code and user address
     /// length : the number of users
+    /// nickname_user :HashMap of reserved nickname and user address
+    /// owner : the manager of the referral rates and moderators
+    /// referral_rates : the share of a fee for each referer level, in basis points
+    /// rewards : HashMap of (user, token) and the claimable referral reward
+    /// reward_total : HashMap of token and the referral rewards not claimed yet
+    /// invitation_codes : HashMap of invitation code and its details
+    /// user_codes : HashMap of user address and the invitation codes it created
+    /// kernel_addr : the address of kernel, accounts it authorizes can moderate users
+    /// allowlist_mode : whether joining requires an invite signed by an approved inviter
+    /// inviters : HashMap of approved inviter address and whether it is approved
+    /// fee_collectors : HashMap of contracts allowed to distribute referral rewards, e.g. erc20_factory
     #[ink(storage)]
     pub struct UsersManage {
         user_info:StorageHashMap<AccountId,User>,
         // user_referer:StorageHashMap<AccountId,AccountId>,
-        code_user:StorageHashMap<u128, AccountId>,
-        length:u128
+        length:u128,
+        nickname_user:StorageHashMap<String, AccountId>,
+        owner:AccountId,
+        referral_rates:Vec<u32>,
+        rewards:StorageHashMap<(AccountId,AccountId),u128>,
+        reward_total:StorageHashMap<AccountId,u128>,
+        invitation_codes:StorageHashMap<String,InvitationCode>,
+        user_codes:StorageHashMap<AccountId,Vec<String>>,
+        kernel_addr:AccountId,
+        allowlist_mode:bool,
+        inviters:StorageHashMap<AccountId,bool>,
+        fee_collectors:StorageHashMap<AccountId,bool>,
+    }
+
+    #[ink(event)]
+    pub struct UserBanned {
+        #[ink(topic)]
+        user: AccountId,
+        #[ink(topic)]
+        moderator: AccountId,
+    }
+
+    #[ink(event)]
+    pub struct UserUnbanned {
+        #[ink(topic)]
+        user: AccountId,
+        #[ink(topic)]
+        moderator: AccountId,
+    }
+
+    #[ink(event)]
+    pub struct InvitationCodeCreated {
+        #[ink(topic)]
+        owner: AccountId,
+        code: String,
+    }
+
+    #[ink(event)]
+    pub struct InvitationCodeRevoked {
+        #[ink(topic)]
+        owner: AccountId,
+        code: String,
+    }
+
+    #[ink(event)]
+    pub struct ReferralRewarded {
+        #[ink(topic)]
+        user: AccountId,
+        #[ink(topic)]
+        token: AccountId,
+        amount: u128,
+    }
+
+    #[ink(event)]
+    pub struct RewardClaimed {
+        #[ink(topic)]
+        user: AccountId,
+        #[ink(topic)]
+        token: AccountId,
+        amount: u128,
     }
 
     impl UsersManage {
@@ -55,25 +155,67 @@ mod users_manage {
         pub fn new() -> Self {
             Self {
                 user_info:StorageHashMap::new(),
-                code_user:StorageHashMap::new(),
                 length:0,
+                nickname_user:StorageHashMap::new(),
+                owner:Self::env().caller(),
+                referral_rates:Vec::new(),
+                rewards:StorageHashMap::new(),
+                reward_total:StorageHashMap::new(),
+                invitation_codes:StorageHashMap::new(),
+                user_codes:StorageHashMap::new(),
+                kernel_addr:AccountId::default(),
+                allowlist_mode:false,
+                inviters:StorageHashMap::new(),
+                fee_collectors:StorageHashMap::new(),
             }
         }
         /// Join the rainbow agreement
-        /// invitation_code:the code of user
+        /// invitation_code:the invitation code of the referer
+        /// name:the name of user
+        /// user_profile:the profile of user
+        /// # Panics
+        /// This invitation_code can be left blank, otherwise it must be usable.
+        /// A non-empty name must not be used by another user.
+        /// Fails in allowlist mode, use join_with_invite instead
+        #[ink(message)]
+        pub fn join(&mut self,invitation_code:String,name:String,user_profile:String) -> bool {
+            assert!(!self.allowlist_mode, "an invite is required");
+            self.insert_user(invitation_code, name, user_profile)
+        }
+        /// Join the rainbow agreement with an invite signed by an approved inviter
+        /// inviter:the address of the approved inviter
+        /// signature:the ECDSA signature of the inviter over invite_hash of the caller
+        /// invitation_code:the invitation code of the referer
         /// name:the name of user
         /// user_profile:the profile of user
         /// # Panics
-        /// This invitation_code can be left blank. The default value is 0
+        /// The inviter must be approved and the signature must be made by the inviter for the caller
+        #[ink(message)]
+        pub fn join_with_invite(&mut self,inviter:AccountId,signature:[u8; 65],invitation_code:String,name:String,user_profile:String) -> bool {
+            assert!(self.is_inviter(inviter), "inviter is not approved");
+            let invite_hash = self.invite_hash(Self::env().caller());
+            let mut public_key = [0u8; 33];
+            assert!(ink_env::ecdsa_recover(&signature, &invite_hash, &mut public_key).is_ok(), "invalid signature");
+            let mut signer = [0u8; 32];
+            ink_env::hash_bytes::<ink_env::hash::Blake2x256>(&public_key, &mut signer);
+            assert!(AccountId::from(signer) == inviter, "invalid signature");
+            self.insert_user(invitation_code, name, user_profile)
+        }
+        /// Get the hash an inviter signs to let a user join in allowlist mode
+        /// user : address of the invited user
         #[ink(message)]
-        pub fn join(&mut self,invitation_code:u128,name:String,user_profile:String) -> bool {
+        pub fn invite_hash(&self,user:AccountId) -> [u8; 32] {
+            let mut output = [0u8; 32];
+            ink_env::hash_encoded::<ink_env::hash::Blake2x256, _>(&(self.env().account_id(), user), &mut output);
+            output
+        }
+        fn insert_user(&mut self,invitation_code:String,name:String,user_profile:String) -> bool {
             assert_eq!(self.length + 1 > self.length, true);
             let caller = Self::env().caller();
             assert_eq!(self.exists_user(caller),false);
-            let code =  self.length + 1;
-            self.code_user.insert(code,caller);
-            let referer = if invitation_code == 0 { AccountId::default()} else { self.get_user_by_code(invitation_code) };
+            let referer = if invitation_code.is_empty() { AccountId::default()} else { self.use_invitation_code(&invitation_code) };
             let nickname = if name.is_empty() { String::default()} else {name };
+            self.reserve_nickname(caller, "", &nickname);
             let profile = if user_profile.is_empty() { String::default()} else {user_profile };
             self.user_info.insert(
                 caller,
@@ -81,10 +223,11 @@ mod users_manage {
                     id:self.length + 1,
                     nickname,
                     profile,
-                    code,
                     address:caller,
                     referer,
-                    childs:Vec::new()
+                    childs:Vec::new(),
+                    active:true,
+                    banned:false
                 }
             );
             self.length += 1;
@@ -97,8 +240,68 @@ mod users_manage {
         /// user : address of user
         #[ink(message)]
         pub fn get_user_referer(&self,user:AccountId) -> AccountId {
-            let user_info : User =  self.user_info.get(&user).unwrap().clone();
-            return user_info.referer;
+            self.user_info.get(&user).map(|user_info| user_info.referer).unwrap_or(AccountId::default())
+        }
+        /// Get the direct subordinates of the user, deactivated users are left out
+        /// user : address of user
+        /// offset : the number of subordinates to skip
+        /// limit : the most subordinates to return
+        #[ink(message)]
+        pub fn get_user_childs(&self,user:AccountId,offset:u32,limit:u32) -> Vec<AccountId> {
+            let childs = self.user_info.get(&user).map(|user_info| user_info.childs.clone()).unwrap_or(Vec::new());
+            childs.into_iter()
+                .filter(|child| self.is_active_user(*child))
+                .skip(offset as usize)
+                .take(limit as usize)
+                .collect()
+        }
+        /// Get the subordinates of the user up to a number of levels, nearest first.
+        /// Deactivated users are left out, but their subordinates are still found
+        /// user : address of user
+        /// levels : 1 for direct subordinates, 2 to include their subordinates and so on
+        #[ink(message)]
+        pub fn get_user_descendants(&self,user:AccountId,levels:u32) -> Vec<AccountId> {
+            let mut descendants = Vec::new();
+            let mut current_level = ink_prelude::vec![user];
+            for _ in 0..levels {
+                let mut next_level = Vec::new();
+                for member in current_level.iter() {
+                    if let Some(user_info) = self.user_info.get(member) {
+                        next_level.extend(user_info.childs.iter().copied());
+                    }
+                }
+                if next_level.is_empty() {
+                    break;
+                }
+                descendants.extend(next_level.iter().copied().filter(|child| self.is_active_user(*child)));
+                current_level = next_level;
+            }
+            descendants
+        }
+        /// Get the superiors of the user up to a number of levels, nearest first.
+        /// Deactivated superiors are left out but still count as a level
+        /// user : address of user
+        /// levels : 1 for the direct superior, 2 to include its superior and so on
+        #[ink(message)]
+        pub fn get_user_ancestors(&self,user:AccountId,levels:u32) -> Vec<AccountId> {
+            let mut ancestors = Vec::new();
+            let mut current = user;
+            for _ in 0..levels {
+                current = self.get_user_referer(current);
+                if current == AccountId::default() {
+                    break;
+                }
+                if self.is_active_user(current) {
+                    ancestors.push(current);
+                }
+            }
+            ancestors
+        }
+        /// Get the number of active users below the user at all levels
+        /// user : address of user
+        #[ink(message)]
+        pub fn get_team_size(&self,user:AccountId) -> u128 {
+            self.get_user_descendants(user, u32::MAX).len() as u128
         }
         /// Check whether the user exists
         /// user : address of user
@@ -108,19 +311,237 @@ mod users_manage {
                 id:0,
                 nickname:String::from(""),
                 profile:String::from(""),
-                code:0,
                 address:AccountId::default(),
                 referer:AccountId::default(),
-                childs:Vec::new()
+                childs:Vec::new(),
+                active:false,
+                banned:false
             };
             let exists_user =  self.user_info.get(&user).unwrap_or(&user_info);
             return exists_user.id !=0 ;
         }
-        /// Get a user by code
-        /// invitation_code : code af user
+        /// Check whether the user exists, has not deactivated the account and is not banned
+        /// user : address of user
+        #[ink(message)]
+        pub fn is_active_user(&self,user:AccountId) -> bool {
+            self.user_info.get(&user).map(|user_info| user_info.active && !user_info.banned).unwrap_or(false)
+        }
+        /// Check whether the user is banned
+        /// user : address of user
+        #[ink(message)]
+        pub fn is_banned(&self,user:AccountId) -> bool {
+            self.user_info.get(&user).map(|user_info| user_info.banned).unwrap_or(false)
+        }
+        /// Get the owner of an invitation code, nobody is found when the code
+        /// can not be used or its owner is deactivated
+        /// invitation_code : the invitation code
+        #[ink(message)]
+        pub fn get_user_by_code(&self,invitation_code:String) -> AccountId {
+            let block = self.env().block_number();
+            match self.invitation_codes.get(&invitation_code) {
+                Some(code) if !code.revoked
+                    && (code.max_uses == 0 || code.uses < code.max_uses)
+                    && code.expire_block.map_or(true, |expire_block| block < expire_block)
+                    && self.is_active_user(code.owner) => code.owner,
+                _ => AccountId::default(),
+            }
+        }
+        /// Create an invitation code chosen by the caller
+        /// invitation_code : the invitation code, at most 32 bytes
+        /// max_uses : the most users that can join with this code, 0 for no limit
+        /// expire_block : the code can not be used from this block on
+        /// # Panics
+        /// The caller must be an active user and the code must never have been created before
+        #[ink(message)]
+        pub fn create_invitation_code(&mut self,invitation_code:String,max_uses:u32,expire_block:Option<u32>) -> bool {
+            assert!(!invitation_code.is_empty() && invitation_code.len() <= MAX_CODE_LENGTH);
+            self.insert_invitation_code(invitation_code, max_uses, expire_block);
+            true
+        }
+        /// Create an invitation code hashed from the caller and a salt
+        /// salt : any bytes, a different salt gives a different code
+        /// max_uses : the most users that can join with this code, 0 for no limit
+        /// expire_block : the code can not be used from this block on
+        /// # Panics
+        /// The caller must be an active user and the code must never have been created before
+        #[ink(message)]
+        pub fn create_hashed_invitation_code(&mut self,salt:Vec<u8>,max_uses:u32,expire_block:Option<u32>) -> String {
+            let invitation_code = self.hash_invitation_code(&salt);
+            self.insert_invitation_code(invitation_code.clone(), max_uses, expire_block);
+            invitation_code
+        }
+        /// Stop accepting an invitation code, the code can not be created again
+        /// invitation_code : the invitation code
+        /// # Panics
+        /// The caller must be the owner of the code
         #[ink(message)]
-        pub fn get_user_by_code(&self,invitation_code:u128) -> AccountId {
-            self.code_user.get(&invitation_code).copied().unwrap_or(AccountId::default())
+        pub fn revoke_invitation_code(&mut self,invitation_code:String) -> bool {
+            let caller = self.env().caller();
+            let code = self.invitation_codes.get_mut(&invitation_code).unwrap();
+            assert_eq!(code.owner, caller);
+            code.revoked = true;
+            self.env().emit_event(InvitationCodeRevoked {
+                owner: caller,
+                code: invitation_code,
+            });
+            true
+        }
+        /// Replace an invitation code by a new hashed one with the same limits,
+        /// the users that joined with the old code are not counted
+        /// invitation_code : the invitation code to revoke
+        /// salt : any bytes, a different salt gives a different code
+        /// # Panics
+        /// The caller must be the owner of the code
+        #[ink(message)]
+        pub fn rotate_invitation_code(&mut self,invitation_code:String,salt:Vec<u8>) -> String {
+            let code = self.invitation_codes.get(&invitation_code).cloned().unwrap();
+            self.revoke_invitation_code(invitation_code);
+            self.create_hashed_invitation_code(salt, code.max_uses, code.expire_block)
+        }
+        /// Get the details of an invitation code
+        /// invitation_code : the invitation code
+        #[ink(message)]
+        pub fn get_invitation_code(&self,invitation_code:String) -> Option<InvitationCode> {
+            self.invitation_codes.get(&invitation_code).cloned()
+        }
+        /// Get the invitation codes created by the user, revoked codes included
+        /// user : address of user
+        #[ink(message)]
+        pub fn list_user_invitation_codes(&self,user:AccountId) -> Vec<String> {
+            self.user_codes.get(&user).cloned().unwrap_or(Vec::new())
+        }
+        /// Get a user by nickname
+        /// name : nickname of user
+        #[ink(message)]
+        pub fn get_user_by_nickname(&self,name:String) -> AccountId {
+            self.nickname_user.get(&name).copied().unwrap_or(AccountId::default())
+        }
+        /// Change the nickname and profile of the caller
+        /// name:the new nickname of user
+        /// user_profile:the new profile of user
+        /// # Panics
+        /// The caller must be an active user and a non-empty name must not be used by another user
+        #[ink(message)]
+        pub fn update_profile(&mut self,name:String,user_profile:String) -> bool {
+            let caller = Self::env().caller();
+            assert!(self.is_active_user(caller));
+            let old_nickname = self.user_info.get(&caller).unwrap().nickname.clone();
+            self.reserve_nickname(caller, &old_nickname, &name);
+            let user_info = self.user_info.get_mut(&caller).unwrap();
+            user_info.nickname = name;
+            user_info.profile = user_profile;
+            true
+        }
+        /// Leave the rainbow agreement, the account no longer counts as a member or referer
+        /// # Panics
+        /// The caller must be an active user
+        #[ink(message)]
+        pub fn deactivate(&mut self) -> bool {
+            let caller = Self::env().caller();
+            assert!(self.is_active_user(caller));
+            self.user_info.get_mut(&caller).unwrap().active = false;
+            true
+        }
+        /// Come back after deactivating the account
+        /// # Panics
+        /// The caller must be a deactivated user
+        #[ink(message)]
+        pub fn reactivate(&mut self) -> bool {
+            let caller = Self::env().caller();
+            let user_info = self.user_info.get_mut(&caller).unwrap();
+            assert!(!user_info.active);
+            user_info.active = true;
+            true
+        }
+        /// Ban a user, a banned user is treated as deactivated until unbanned
+        /// user : address of user
+        /// # Panics
+        /// The caller must be authorized to moderate users and the user must not be banned
+        #[ink(message)]
+        pub fn ban_user(&mut self,user:AccountId) -> bool {
+            let moderator = Self::env().caller();
+            self.only_authorized(moderator);
+            let user_info = self.user_info.get_mut(&user).unwrap();
+            assert!(!user_info.banned);
+            user_info.banned = true;
+            self.env().emit_event(UserBanned { user, moderator });
+            true
+        }
+        /// Lift the ban of a user
+        /// user : address of user
+        /// # Panics
+        /// The caller must be authorized to moderate users and the user must be banned
+        #[ink(message)]
+        pub fn unban_user(&mut self,user:AccountId) -> bool {
+            let moderator = Self::env().caller();
+            self.only_authorized(moderator);
+            let user_info = self.user_info.get_mut(&user).unwrap();
+            assert!(user_info.banned);
+            user_info.banned = false;
+            self.env().emit_event(UserUnbanned { user, moderator });
+            true
+        }
+        /// Replace the nickname of a user, an empty name removes it
+        /// user : address of user
+        /// name : the new nickname of user
+        /// # Panics
+        /// The caller must be authorized to moderate users and a non-empty name must not be used by another user
+        #[ink(message)]
+        pub fn set_user_nickname(&mut self,user:AccountId,name:String) -> bool {
+            self.only_authorized(Self::env().caller());
+            let old_nickname = self.user_info.get(&user).unwrap().nickname.clone();
+            self.reserve_nickname(user, &old_nickname, &name);
+            self.user_info.get_mut(&user).unwrap().nickname = name;
+            true
+        }
+        /// Turn allowlist mode on or off
+        /// enabled : whether joining requires an invite signed by an approved inviter
+        /// # Panics
+        /// The caller must be authorized to moderate users
+        #[ink(message)]
+        pub fn set_allowlist_mode(&mut self,enabled:bool) -> bool {
+            self.only_authorized(Self::env().caller());
+            self.allowlist_mode = enabled;
+            true
+        }
+        /// Get whether joining requires an invite signed by an approved inviter
+        #[ink(message)]
+        pub fn get_allowlist_mode(&self) -> bool {
+            self.allowlist_mode
+        }
+        /// Approve or remove an inviter for allowlist mode
+        /// inviter : the address derived from the ECDSA public key of the inviter
+        /// approved : whether the inviter may sign invites
+        /// # Panics
+        /// The caller must be authorized to moderate users
+        #[ink(message)]
+        pub fn set_inviter(&mut self,inviter:AccountId,approved:bool) -> bool {
+            self.only_authorized(Self::env().caller());
+            if approved {
+                self.inviters.insert(inviter, true);
+            } else {
+                self.inviters.take(&inviter);
+            }
+            true
+        }
+        /// Check whether an inviter is approved
+        /// inviter : the address of the inviter
+        #[ink(message)]
+        pub fn is_inviter(&self,inviter:AccountId) -> bool {
+            self.inviters.get(&inviter).copied().unwrap_or(false)
+        }
+        /// Let the kernel decide who can moderate users
+        /// kernel_addr : the address of kernel
+        #[ink(message)]
+        pub fn set_kernel(&mut self,kernel_addr:AccountId) -> bool {
+            self.only_owner(Self::env().caller());
+            self.kernel_addr = kernel_addr;
+            true
+        }
+        /// Get the address of kernel
+        #[ink(message)]
+        pub fn get_kernel(&self) -> AccountId {
+            self.kernel_addr
         }
         /// Show all users
         #[ink(message)]
@@ -140,8 +561,186 @@ mod users_manage {
         pub fn get_user_info(&self,user:AccountId) -> User {
             self.user_info.get(&user).unwrap().clone()
         }
+        /// Set the share of a fee paid to each referer level
+        /// rates : basis points for the direct referer first, then its referer and so on
+        /// # Panics
+        /// The caller must be the owner and the rates must not add up to more than 10000
+        #[ink(message)]
+        pub fn set_referral_rates(&mut self,rates:Vec<u32>) -> bool {
+            self.only_owner(self.env().caller());
+            let total = rates.iter().fold(0u32, |total, rate| total.saturating_add(*rate));
+            assert!(total <= MAX_REFERRAL_RATE);
+            self.referral_rates = rates;
+            true
+        }
+        /// Get the share of a fee paid to each referer level, in basis points
+        #[ink(message)]
+        pub fn get_referral_rates(&self) -> Vec<u32> {
+            self.referral_rates.clone()
+        }
+        /// Allow or stop a contract collecting fees to distribute referral rewards
+        /// collector : the address of the contract, e.g. erc20_factory
+        /// approved : whether the contract may distribute referral rewards
+        /// # Panics
+        /// The caller must be the owner
+        #[ink(message)]
+        pub fn set_fee_collector(&mut self,collector:AccountId,approved:bool) -> bool {
+            self.only_owner(self.env().caller());
+            if approved {
+                self.fee_collectors.insert(collector, true);
+            } else {
+                self.fee_collectors.take(&collector);
+            }
+            true
+        }
+        /// Check whether a contract may distribute referral rewards
+        /// collector : the address of the contract
+        #[ink(message)]
+        pub fn is_fee_collector(&self,collector:AccountId) -> bool {
+            self.fee_collectors.get(&collector).copied().unwrap_or(false)
+        }
+        /// Get the part of a fee that goes to the referers of the payer
+        /// payer : address of the user paying the fee
+        /// amount : the fee
+        #[ink(message)]
+        pub fn referral_share(&self,payer:AccountId,amount:u128) -> u128 {
+            self.referral_rewards(payer, amount).iter().map(|(_, reward)| reward).sum()
+        }
+        /// Credit the referers of the payer with their share of a fee.
+        /// The share must already have been transferred to this contract
+        /// payer : address of the user paying the fee
+        /// token : the token the fee is paid in
+        /// amount : the whole fee
+        /// # Panics
+        /// The caller must be a fee collector and the token balance of this contract
+        /// not yet owed to referers must cover the share
+        #[ink(message)]
+        pub fn distribute_referral_reward(&mut self,payer:AccountId,token:AccountId,amount:u128) -> u128 {
+            assert!(self.is_fee_collector(self.env().caller()), "not a fee collector");
+            let rewards = self.referral_rewards(payer, amount);
+            let share:u128 = rewards.iter().map(|(_, reward)| reward).sum();
+            if share == 0 {
+                return 0;
+            }
+            let erc20_instance: Erc20 = ink_env::call::FromAccountId::from_account_id(token);
+            let balance = erc20_instance.balance_of(self.env().account_id());
+            let reward_total = self.reward_total.entry(token).or_insert(0);
+            assert!(balance.saturating_sub(*reward_total) >= share, "referral reward not paid");
+            *reward_total += share;
+            for (user, reward) in rewards {
+                *self.rewards.entry((user, token)).or_insert(0) += reward;
+                self.env().emit_event(ReferralRewarded {
+                    user,
+                    token,
+                    amount: reward,
+                });
+            }
+            share
+        }
+        /// Get the claimable referral reward of a user
+        /// user : address of user
+        /// token : the token of the reward
+        #[ink(message)]
+        pub fn get_reward(&self,user:AccountId,token:AccountId) -> u128 {
+            self.rewards.get(&(user, token)).copied().unwrap_or(0)
+        }
+        /// Transfer the referral reward of the caller
+        /// token : the token of the reward
+        /// # Panics
+        /// The caller must have a reward in this token
+        #[ink(message)]
+        pub fn claim_reward(&mut self,token:AccountId) -> u128 {
+            let caller = self.env().caller();
+            let amount = self.rewards.take(&(caller, token)).unwrap_or(0);
+            assert!(amount > 0);
+            let reward_total = self.reward_total.get_mut(&token).unwrap();
+            *reward_total -= amount;
+            let mut erc20_instance: Erc20 = ink_env::call::FromAccountId::from_account_id(token);
+            assert!(erc20_instance.transfer(caller, amount));
+            self.env().emit_event(RewardClaimed {
+                user: caller,
+                token,
+                amount,
+            });
+            amount
+        }
+        /// Split a fee between the referers of the payer, deactivated referers get nothing
+        fn referral_rewards(&self,payer:AccountId,amount:u128) -> Vec<(AccountId,u128)> {
+            let mut rewards = Vec::new();
+            let mut current = payer;
+            for rate in self.referral_rates.iter() {
+                current = self.get_user_referer(current);
+                if current == AccountId::default() {
+                    break;
+                }
+                let reward = amount.saturating_mul(*rate as u128) / MAX_REFERRAL_RATE as u128;
+                if self.is_active_user(current) && reward > 0 {
+                    rewards.push((current, reward));
+                }
+            }
+            rewards
+        }
+        fn insert_invitation_code(&mut self,invitation_code:String,max_uses:u32,expire_block:Option<u32>) {
+            let caller = self.env().caller();
+            assert!(self.is_active_user(caller));
+            assert!(!self.invitation_codes.contains_key(&invitation_code), "invitation code is taken");
+            self.invitation_codes.insert(invitation_code.clone(), InvitationCode {
+                owner: caller,
+                max_uses,
+                uses: 0,
+                expire_block,
+                revoked: false,
+            });
+            self.user_codes.entry(caller).or_insert(Vec::new()).push(invitation_code.clone());
+            self.env().emit_event(InvitationCodeCreated {
+                owner: caller,
+                code: invitation_code,
+            });
+        }
+        fn use_invitation_code(&mut self,invitation_code:&str) -> AccountId {
+            let invitation_code = String::from(invitation_code);
+            let referer = self.get_user_by_code(invitation_code.clone());
+            assert!(referer != AccountId::default(), "invalid invitation code");
+            self.invitation_codes.get_mut(&invitation_code).unwrap().uses += 1;
+            referer
+        }
+        fn hash_invitation_code(&self,salt:&[u8]) -> String {
+            let mut output = <ink_env::hash::Blake2x256 as ink_env::hash::HashOutput>::Type::default();
+            ink_env::hash_encoded::<ink_env::hash::Blake2x256, _>(&(self.env().caller(), salt), &mut output);
+            const HEX: &[u8; 16] = b"0123456789abcdef";
+            let mut invitation_code = String::new();
+            for byte in output.iter().take(HASHED_CODE_BYTES) {
+                invitation_code.push(HEX[(byte >> 4) as usize] as char);
+                invitation_code.push(HEX[(byte & 0x0f) as usize] as char);
+            }
+            invitation_code
+        }
+        fn only_owner(&self,sender:AccountId) {
+            assert_eq!(self.owner, sender);
+        }
+        fn only_authorized(&self,sender:AccountId) {
+            if self.owner != AccountId::default() && self.owner == sender {
+                return;
+            }
+            assert!(self.kernel_addr != AccountId::default());
+            let kernel_instance: Kernel = ink_env::call::FromAccountId::from_account_id(self.kernel_addr);
+            assert!(kernel_instance.is_authorized(sender, String::from(MODERATE_PRIVILEGE)));
+        }
+        fn reserve_nickname(&mut self,user:AccountId,old_nickname:&str,new_nickname:&str) {
+            if old_nickname == new_nickname {
+                return;
+            }
+            if !new_nickname.is_empty() {
+                let new_nickname = String::from(new_nickname);
+                assert!(!self.nickname_user.contains_key(&new_nickname), "nickname is taken");
+                self.nickname_user.insert(new_nickname, user);
+            }
+            if !old_nickname.is_empty() {
+                self.nickname_user.take(&String::from(old_nickname));
+            }
+        }
         fn insert_user_child(&mut self,user:AccountId,child:AccountId) -> bool {
-            let mut user_info = self.user_info.get_mut(&user).unwrap().clone();
+            let user_info = self.user_info.get_mut(&user).unwrap();
             user_info.childs.push(child);
             true
         }
@@ -162,10 +761,243 @@ mod users_manage {
                 ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
                     .expect("Cannot get accounts");
             let mut users_manage = UsersManage::new();
-            users_manage.join(0,String::from("test"),String::from("test"));
-            assert!(users_manage.get_user_by_code(1) != AccountId::default());
+            users_manage.join(String::new(),String::from("test"),String::from("test"));
+            users_manage.create_invitation_code(String::from("test"),0,None);
+            assert!(users_manage.get_user_by_code(String::from("test")) != AccountId::default());
             assert!(users_manage.exists_user(accounts.alice) == true);
             assert!(users_manage.get_user_referer(accounts.alice) == AccountId::default());
         }
+
+        fn set_caller(caller: AccountId) {
+            let callee = ink_env::account_id::<ink_env::DefaultEnvironment>()
+                .unwrap_or([0x0; 32].into());
+            ink_env::test::push_execution_context::<ink_env::DefaultEnvironment>(
+                caller,
+                callee,
+                1000000,
+                1000000,
+                ink_env::test::CallData::new(ink_env::call::Selector::new([0x00; 4])),
+            );
+        }
+
+        #[ink::test]
+        fn referral_tree_works() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                    .expect("Cannot get accounts");
+            let mut users_manage = UsersManage::new();
+            users_manage.join(String::new(),String::from("alice"),String::new());
+            users_manage.create_invitation_code(String::from("alice"),0,None);
+            set_caller(accounts.bob);
+            users_manage.join(String::from("alice"),String::from("bob"),String::new());
+            users_manage.create_invitation_code(String::from("bob"),0,None);
+            set_caller(accounts.charlie);
+            users_manage.join(String::from("bob"),String::from("charlie"),String::new());
+            set_caller(accounts.django);
+            users_manage.join(String::from("alice"),String::from("django"),String::new());
+            assert!(users_manage.get_user_childs(accounts.alice,0,10) == ink_prelude::vec![accounts.bob, accounts.django]);
+            assert!(users_manage.get_user_childs(accounts.alice,1,10) == ink_prelude::vec![accounts.django]);
+            assert_eq!(users_manage.get_user_descendants(accounts.alice,1).len(), 2);
+            assert_eq!(users_manage.get_user_descendants(accounts.alice,2).len(), 3);
+            assert!(users_manage.get_user_ancestors(accounts.charlie,5) == ink_prelude::vec![accounts.bob, accounts.alice]);
+            assert_eq!(users_manage.get_team_size(accounts.alice), 3);
+            set_caller(accounts.bob);
+            users_manage.deactivate();
+            assert_eq!(users_manage.get_team_size(accounts.alice), 2);
+            assert!(users_manage.get_user_ancestors(accounts.charlie,5) == ink_prelude::vec![accounts.alice]);
+        }
+
+        #[ink::test]
+        fn update_profile_works() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                    .expect("Cannot get accounts");
+            let mut users_manage = UsersManage::new();
+            users_manage.join(String::new(),String::from("test"),String::from("test"));
+            assert!(users_manage.get_user_by_nickname(String::from("test")) == accounts.alice);
+            users_manage.update_profile(String::from("rainbow"),String::from("profile"));
+            assert!(users_manage.get_user_by_nickname(String::from("test")) == AccountId::default());
+            assert!(users_manage.get_user_by_nickname(String::from("rainbow")) == accounts.alice);
+            assert!(users_manage.get_user_info(accounts.alice).profile == String::from("profile"));
+        }
+
+        #[ink::test]
+        fn deactivate_works() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                    .expect("Cannot get accounts");
+            let mut users_manage = UsersManage::new();
+            users_manage.join(String::new(),String::from("test"),String::from("test"));
+            users_manage.create_invitation_code(String::from("test"),0,None);
+            users_manage.deactivate();
+            assert!(!users_manage.is_active_user(accounts.alice));
+            assert!(users_manage.exists_user(accounts.alice));
+            assert!(users_manage.get_user_by_code(String::from("test")) == AccountId::default());
+            users_manage.reactivate();
+            assert!(users_manage.is_active_user(accounts.alice));
+            assert!(users_manage.get_user_by_code(String::from("test")) == accounts.alice);
+        }
+
+        #[ink::test]
+        fn invitation_code_limits_work() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                    .expect("Cannot get accounts");
+            let mut users_manage = UsersManage::new();
+            users_manage.join(String::new(),String::from("alice"),String::new());
+            users_manage.create_invitation_code(String::from("once"),1,None);
+            users_manage.create_invitation_code(String::from("soon"),0,Some(1));
+            let hashed_code = users_manage.create_hashed_invitation_code(ink_prelude::vec![1, 2, 3],0,None);
+            assert_eq!(hashed_code.len(), 16);
+            assert_eq!(users_manage.list_user_invitation_codes(accounts.alice).len(), 3);
+            set_caller(accounts.bob);
+            users_manage.join(String::from("once"),String::from("bob"),String::new());
+            assert!(users_manage.get_user_by_code(String::from("once")) == AccountId::default());
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>().expect("Cannot advance block");
+            assert!(users_manage.get_user_by_code(String::from("soon")) == AccountId::default());
+            set_caller(accounts.alice);
+            let rotated_code = users_manage.rotate_invitation_code(hashed_code.clone(),ink_prelude::vec![4, 5, 6]);
+            assert!(users_manage.get_user_by_code(hashed_code) == AccountId::default());
+            assert!(users_manage.get_user_by_code(rotated_code) == accounts.alice);
+        }
+
+        #[ink::test]
+        #[should_panic]
+        fn join_fails_with_revoked_code() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                    .expect("Cannot get accounts");
+            let mut users_manage = UsersManage::new();
+            users_manage.join(String::new(),String::from("alice"),String::new());
+            users_manage.create_invitation_code(String::from("alice"),0,None);
+            users_manage.revoke_invitation_code(String::from("alice"));
+            set_caller(accounts.bob);
+            users_manage.join(String::from("alice"),String::from("bob"),String::new());
+        }
+
+        #[ink::test]
+        fn moderation_works() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                    .expect("Cannot get accounts");
+            let mut users_manage = UsersManage::new();
+            set_caller(accounts.bob);
+            users_manage.join(String::new(),String::from("abuse"),String::new());
+            set_caller(accounts.alice);
+            users_manage.set_user_nickname(accounts.bob,String::from("bob"));
+            assert!(users_manage.get_user_by_nickname(String::from("abuse")) == AccountId::default());
+            assert!(users_manage.get_user_by_nickname(String::from("bob")) == accounts.bob);
+            users_manage.ban_user(accounts.bob);
+            assert!(users_manage.is_banned(accounts.bob));
+            assert!(!users_manage.is_active_user(accounts.bob));
+            users_manage.unban_user(accounts.bob);
+            assert!(users_manage.is_active_user(accounts.bob));
+        }
+
+        #[ink::test]
+        #[should_panic]
+        fn ban_user_fails_without_kernel() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                    .expect("Cannot get accounts");
+            let mut users_manage = UsersManage::new();
+            users_manage.join(String::new(),String::from("alice"),String::new());
+            set_caller(accounts.bob);
+            users_manage.ban_user(accounts.alice);
+        }
+
+        #[ink::test]
+        #[should_panic]
+        fn join_fails_in_allowlist_mode() {
+            let mut users_manage = UsersManage::new();
+            users_manage.set_allowlist_mode(true);
+            users_manage.join(String::new(),String::from("alice"),String::new());
+        }
+
+        fn sign_invite(users_manage: &UsersManage, user: AccountId) -> (AccountId, [u8; 65]) {
+            let secret_key = secp256k1::SecretKey::parse(&[0x01; 32]).expect("Cannot parse secret key");
+            let public_key = secp256k1::PublicKey::from_secret_key(&secret_key).serialize_compressed();
+            let mut inviter = [0u8; 32];
+            ink_env::hash_bytes::<ink_env::hash::Blake2x256>(&public_key, &mut inviter);
+            let message = secp256k1::Message::parse(&users_manage.invite_hash(user));
+            let (signature, recovery_id) = secp256k1::sign(&message, &secret_key);
+            let mut invite = [0u8; 65];
+            invite[..64].copy_from_slice(&signature.serialize());
+            invite[64] = recovery_id.serialize();
+            (AccountId::from(inviter), invite)
+        }
+
+        #[ink::test]
+        fn join_with_invite_works() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                    .expect("Cannot get accounts");
+            let mut users_manage = UsersManage::new();
+            users_manage.set_allowlist_mode(true);
+            let (inviter, signature) = sign_invite(&users_manage, accounts.bob);
+            users_manage.set_inviter(inviter, true);
+            set_caller(accounts.bob);
+            assert!(users_manage.join_with_invite(inviter,signature,String::new(),String::from("bob"),String::new()));
+            assert!(users_manage.exists_user(accounts.bob));
+        }
+
+        #[ink::test]
+        #[should_panic(expected = "invalid signature")]
+        fn join_with_invite_fails_for_other_user() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                    .expect("Cannot get accounts");
+            let mut users_manage = UsersManage::new();
+            users_manage.set_allowlist_mode(true);
+            let (inviter, signature) = sign_invite(&users_manage, accounts.bob);
+            users_manage.set_inviter(inviter, true);
+            set_caller(accounts.charlie);
+            users_manage.join_with_invite(inviter,signature,String::new(),String::from("charlie"),String::new());
+        }
+
+        #[ink::test]
+        fn referral_share_works() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                    .expect("Cannot get accounts");
+            let mut users_manage = UsersManage::new();
+            users_manage.set_referral_rates(ink_prelude::vec![1000, 500]);
+            users_manage.join(String::new(),String::from("alice"),String::new());
+            users_manage.create_invitation_code(String::from("alice"),0,None);
+            set_caller(accounts.bob);
+            users_manage.join(String::from("alice"),String::from("bob"),String::new());
+            users_manage.create_invitation_code(String::from("bob"),0,None);
+            set_caller(accounts.charlie);
+            users_manage.join(String::from("bob"),String::from("charlie"),String::new());
+            assert_eq!(users_manage.referral_share(accounts.alice,1000), 0);
+            assert_eq!(users_manage.referral_share(accounts.bob,1000), 100);
+            assert_eq!(users_manage.referral_share(accounts.charlie,1000), 150);
+            users_manage.deactivate();
+            set_caller(accounts.bob);
+            users_manage.deactivate();
+            assert_eq!(users_manage.referral_share(accounts.charlie,1000), 50);
+        }
+
+        #[ink::test]
+        #[should_panic]
+        fn set_referral_rates_fails_above_total() {
+            let mut users_manage = UsersManage::new();
+            users_manage.set_referral_rates(ink_prelude::vec![6000, 5000]);
+        }
+
+        #[ink::test]
+        #[should_panic(expected = "not a fee collector")]
+        fn distribute_referral_reward_fails_for_others() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                    .expect("Cannot get accounts");
+            let mut users_manage = UsersManage::new();
+            users_manage.set_fee_collector(accounts.django, true);
+            assert!(users_manage.is_fee_collector(accounts.django));
+            users_manage.set_fee_collector(accounts.django, false);
+            assert!(!users_manage.is_fee_collector(accounts.django));
+            set_caller(accounts.eve);
+            users_manage.distribute_referral_reward(accounts.bob, accounts.frank, 1000);
+        }
     }
 }